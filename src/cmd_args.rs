@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
 //cargo run -- --help
 #[derive(Parser, Debug)]
@@ -23,4 +23,32 @@ pub struct Args {
 
     #[arg(short, long, default_value_t = false, help = "docs or how to use")]
     pub docs: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Fill tables with made up records and exit
+    Seed {
+        #[arg(
+            required = true,
+            help = "Tables in order, `users:50` or `users` for 10 records"
+        )]
+        tables: Vec<String>,
+
+        #[arg(
+            long,
+            help = "Seed of the generator, the same seed makes the same records"
+        )]
+        seed: Option<u64>,
+
+        #[arg(
+            long,
+            default_value_t = false,
+            help = "Delete the records already in the tables"
+        )]
+        replace: bool,
+    },
 }
@@ -4,9 +4,39 @@ use crate::model::auth::Keys;
 
 pub const FOLDER_NAME: &str = "aginisi";
 pub const UPLOAD_FOLDER_NAME: &str = "aginisi_file";
+pub const SCHEMA_FOLDER_NAME: &str = "_schemas"; // inside FOLDER_NAME
 
 pub const AUTH_TABLE_NAME: &str = "auth";
 
+// fields managed for tables with `timestamps` and `soft_delete`
+pub const CREATED_AT: &str = "created_at";
+pub const UPDATED_AT: &str = "updated_at";
+pub const DELETED_AT: &str = "deleted_at";
+
+// query parameters that are options rather than field filters
+pub const RESERVED_PARAMS: &[&str] = &[
+    "limit",
+    "offset",
+    "envelope",
+    "upsert",
+    "where",
+    "select",
+    "q",
+    "highlight",
+    "_sort",
+    "_fields",
+    "_expand",
+    "_embed",
+    "_group_by",
+    "_sum",
+    "_avg",
+    "_min",
+    "_max",
+    "_distinct",
+    "include_deleted",
+    "only_deleted",
+];
+
 pub static KEYS: LazyLock<Keys> = LazyLock::new(|| {
     // let secret = std::env::var("JWT_SECRET").expect("JWT_SECRET must be set");
     let secret = "runRUNrunRUNrunRunRUNrunRUNrunRunRUNrunRUNrunRunRUNrunRUNrunRunRUNrunRUNrunRunRUNrunRUNrun".to_string();
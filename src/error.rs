@@ -0,0 +1,36 @@
+use axum::{Json, http::StatusCode};
+use serde_json::{Value, json};
+
+#[derive(Debug)]
+pub enum AppError {
+    NotFound,
+    BadRequest(String),
+    Conflict(String),
+    Invalid(Vec<Value>), // schema violations, each with a json pointer and a message
+    UnsupportedMediaType(String),
+}
+
+impl From<AppError> for (StatusCode, Json<Value>) {
+    fn from(err: AppError) -> Self {
+        match err {
+            AppError::NotFound => (
+                StatusCode::NOT_FOUND,
+                Json(json!({"message":"Record not found"})),
+            ),
+            AppError::BadRequest(message) => {
+                (StatusCode::BAD_REQUEST, Json(json!({ "message": message })))
+            }
+            AppError::Conflict(message) => {
+                (StatusCode::CONFLICT, Json(json!({ "message": message })))
+            }
+            AppError::Invalid(errors) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({ "message": "Validation failed", "errors": errors })),
+            ),
+            AppError::UnsupportedMediaType(message) => (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                Json(json!({ "message": message })),
+            ),
+        }
+    }
+}
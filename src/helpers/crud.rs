@@ -1,58 +1,623 @@
-use serde_json::{Value, json};
+use std::collections::HashSet;
 
-use super::json::{read_json, write_to_json};
+use chrono::{SecondsFormat, Utc};
+use json_patch::Patch;
+use serde_json::{Map, Value, json};
+use ulid::Ulid;
+use uuid::Uuid;
+
+use crate::consts::{CREATED_AT, DELETED_AT, UPDATED_AT};
+use crate::error::AppError;
+use crate::model::filter_type::FilterType;
+use crate::model::toml_config::{IdStrategy, TableConfig};
+
+use super::integrity::{apply_delete, check_references, plan_delete};
+use super::store::TableStore;
+
+// ids arrive as path segments, so string and number keys are compared by how they print
+pub fn id_string(value: Option<&Value>) -> Option<String> {
+    match value {
+        Some(Value::String(id)) => Some(id.clone()),
+        Some(Value::Number(id)) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+fn id_matches(obj: &Value, key: &str, id: &str) -> bool {
+    id_string(obj.get(key)).is_some_and(|v| v == id)
+}
+
+// the stored form of an id taken from a path. auto increment keys stay numbers
+fn id_value(config: &TableConfig, id: &str) -> Result<Value, AppError> {
+    match (config.id_strategy(), id.parse::<u64>()) {
+        (IdStrategy::AutoIncrement, Ok(id)) => Ok(json!(id)),
+        (IdStrategy::AutoIncrement, Err(_)) => Err(AppError::BadRequest(format!(
+            "{} must be a number",
+            config.primary_key()
+        ))),
+        _ => Ok(json!(id)),
+    }
+}
+
+fn now() -> Value {
+    json!(Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true))
+}
+
+// ! timestamps
+// `updated_at` is set on every write and `created_at` is kept from `old`. a new
+// record may bring its own times, which imports and seeds rely on
+fn touch(config: &TableConfig, old: Option<&Value>, record: &mut Value) {
+    let Value::Object(map) = record else {
+        return;
+    };
+    if !config.timestamps() {
+        return;
+    }
+    let now = now();
+    match old {
+        Some(old) => {
+            let created = old
+                .get(CREATED_AT)
+                .or(map.get(CREATED_AT))
+                .cloned()
+                .unwrap_or_else(|| now.clone());
+            map.insert(CREATED_AT.to_string(), created);
+            map.insert(UPDATED_AT.to_string(), now);
+        }
+        None => {
+            let created = map.entry(CREATED_AT).or_insert(now).clone();
+            map.entry(UPDATED_AT).or_insert(created);
+        }
+    }
+}
+
+/// Whether `row` is soft deleted. Always false for tables without `soft_delete`.
+pub fn is_deleted(config: &TableConfig, row: &Value) -> bool {
+    config.soft_delete() && row.get(DELETED_AT).is_some_and(|at| !at.is_null())
+}
+
+// sets `deleted_at` on a row that is not deleted yet
+pub fn mark_deleted(config: &TableConfig, row: &mut Value) {
+    if !row.is_object() || is_deleted(config, row) {
+        return;
+    }
+    let now = now();
+    if config.timestamps() {
+        row[UPDATED_AT] = now.clone();
+    }
+    row[DELETED_AT] = now;
+}
 
 // ! create data
-pub fn create_data(file_name: &str, mut item: Value) -> Value {
-    let mut data = read_json(file_name);
-    if let Value::Array(arr) = &mut data {
-        let next_id = arr
+pub fn create_data(store: &TableStore, file_name: &str, item: Value) -> Result<Value, AppError> {
+    Ok(create_records(store, file_name, vec![item], false)?
+        .pop()
+        .unwrap_or_else(|| json!({})))
+}
+
+// ! create many
+// inserts every item in one go. records without a primary key get one from the
+// table's id strategy, and a key that is already taken is a conflict
+pub fn create_many(
+    store: &TableStore,
+    file_name: &str,
+    items: Vec<Value>,
+) -> Result<Vec<Value>, AppError> {
+    create_records(store, file_name, items, true)
+}
+
+// schema violations of a list are pointed at by the item's index
+fn create_records(
+    store: &TableStore,
+    file_name: &str,
+    mut items: Vec<Value>,
+    list: bool,
+) -> Result<Vec<Value>, AppError> {
+    let config = store.config(file_name);
+    let key = config.primary_key();
+    let schema = store.schema(file_name);
+    for item in items.iter_mut() {
+        if let Some(schema) = schema {
+            schema.apply_defaults(item);
+        }
+        check_references(store, file_name, item)?;
+    }
+
+    store.insert(file_name, |arr| {
+        let mut taken: HashSet<String> = arr.iter().filter_map(|v| id_string(v.get(key))).collect();
+        let mut max_id = arr
             .iter()
-            .filter_map(|v| v.get("id"))
+            .filter_map(|v| v.get(key))
             .filter_map(|id| id.as_u64())
             .max()
-            .unwrap_or(0)
-            + 1;
+            .unwrap_or(0);
 
-        if let Value::Object(map) = &mut item {
-            if let None = map.get("id") {
-                map.insert("id".to_string(), json!(next_id));
+        let mut created = Vec::with_capacity(items.len());
+        let mut violations = vec![];
+        for (index, mut item) in items.into_iter().enumerate() {
+            let Value::Object(map) = &mut item else {
+                return Err(AppError::BadRequest(
+                    "A record must be a json object".to_string(),
+                ));
+            };
+            if !map.contains_key(key) {
+                let id = match config.id_strategy() {
+                    IdStrategy::AutoIncrement => json!(max_id + 1),
+                    IdStrategy::UuidV4 => json!(Uuid::new_v4().to_string()),
+                    IdStrategy::UuidV7 => json!(Uuid::now_v7().to_string()),
+                    IdStrategy::Ulid => json!(Ulid::new().to_string()),
+                    IdStrategy::Client => {
+                        return Err(AppError::BadRequest(format!("Missing {} in record", key)));
+                    }
+                };
+                map.insert(key.to_string(), id);
             }
+            touch(config, None, &mut item);
+
+            let Some(id) = id_string(item.get(key)) else {
+                return Err(AppError::BadRequest(format!(
+                    "{} must be a string or a number",
+                    key
+                )));
+            };
+            max_id = max_id.max(item.get(key).and_then(Value::as_u64).unwrap_or(0));
+            if !taken.insert(id.clone()) {
+                return Err(AppError::Conflict(format!(
+                    "A record with {} {} already exists",
+                    key, id
+                )));
+            }
+            if let Some(schema) = schema {
+                let prefix = match list {
+                    true => format!("/{}", index),
+                    false => String::new(),
+                };
+                violations.extend(schema.violations(&item, &prefix));
+            }
+            created.push(item);
         }
-        arr.push(item.clone());
-        write_to_json(file_name, &data);
-        return item;
-    }
-    return json!({});
+        if !violations.is_empty() {
+            return Err(AppError::Invalid(violations));
+        }
+        Ok(created)
+    })
+}
 
-    // if let Value::Object(map) = &mut data {
-    //     map.insert("1".to_string(), item);
-    // }
+// ! find data
+pub fn find_data(store: &TableStore, file_name: &str, id: &str) -> Option<Value> {
+    let key = store.config(file_name).primary_key();
+    store.read(file_name, |arr| {
+        arr.iter().find(|obj| id_matches(obj, key, id)).cloned()
+    })
 }
 
 // ! update data
-pub fn update_data(file_name: &str, id: u64, key: &str, new_value: Value) -> Value {
-    let mut data = read_json(file_name);
-    let mut res = json!({});
-    if let Value::Array(arr) = &mut data {
-        for obj in arr.iter_mut() {
-            if obj.get("id") == Some(&Value::Number(id.into())) {
-                obj[key] = new_value.clone();
-                res = obj.clone();
+// applies `patch` as an RFC 7396 merge patch: every key is set, `null` removes
+// the key and nested objects are merged. the id cannot be patched
+pub fn update_data(
+    store: &TableStore,
+    file_name: &str,
+    id: &str,
+    patch: &Value,
+) -> Result<Value, AppError> {
+    check_references(store, file_name, patch)?;
+    modify_record(store, file_name, id, |obj| {
+        json_patch::merge(obj, patch);
+        Ok(())
+    })
+}
+
+// ! patch data
+// applies an RFC 6902 json patch (add/remove/replace/move/copy/test). the
+// operations are atomic: when one fails the record is left untouched
+pub fn patch_data(
+    store: &TableStore,
+    file_name: &str,
+    id: &str,
+    operations: Value,
+) -> Result<Value, AppError> {
+    let patch: Patch = serde_json::from_value(operations)
+        .map_err(|e| AppError::BadRequest(format!("Invalid json patch: {}", e)))?;
+
+    // the changed fields are checked on a copy, a failing patch is reported below
+    if let Some(current) = find_data(store, file_name, id) {
+        let mut preview = current.clone();
+        if json_patch::patch(&mut preview, &patch).is_ok()
+            && let Value::Object(fields) = preview
+        {
+            let changed: Map<String, Value> = fields
+                .into_iter()
+                .filter(|(field, value)| current.get(field) != Some(value))
+                .collect();
+            check_references(store, file_name, &Value::Object(changed))?;
+        }
+    }
+    modify_record(store, file_name, id, |obj| {
+        json_patch::patch(obj, &patch).map_err(|e| AppError::Conflict(e.to_string()))
+    })
+}
+
+fn modify_record(
+    store: &TableStore,
+    file_name: &str,
+    id: &str,
+    apply: impl FnOnce(&mut Value) -> Result<(), AppError>,
+) -> Result<Value, AppError> {
+    let config = store.config(file_name);
+    let key = config.primary_key();
+    let schema = store.schema(file_name);
+    store.write(file_name, |arr| {
+        let obj = arr
+            .iter_mut()
+            .find(|obj| id_matches(obj, key, id))
+            .ok_or(AppError::NotFound)?;
+
+        let mut updated = obj.clone();
+        apply(&mut updated)?;
+        if !updated.is_object() {
+            return Err(AppError::BadRequest(
+                "A record must stay a json object".to_string(),
+            ));
+        }
+        updated[key] = obj[key].clone();
+        touch(config, Some(obj), &mut updated);
+        if let Some(schema) = schema {
+            let violations = schema.violations(&updated, "");
+            if !violations.is_empty() {
+                return Err(AppError::Invalid(violations));
             }
         }
-        write_to_json(file_name, &data);
+        *obj = updated.clone();
+        Ok(updated)
+    })
+}
 
-        return res;
-    } else {
-        return json!({});
+// ! replace data
+// swaps the whole record for `item`, keeping its id. when the id does not
+// exist the record is created only if `upsert` is set
+pub fn replace_data(
+    store: &TableStore,
+    file_name: &str,
+    id: &str,
+    mut item: Value,
+    upsert: bool,
+) -> Result<Value, AppError> {
+    let config = store.config(file_name);
+    let key = config.primary_key();
+    let schema = store.schema(file_name);
+    if let Some(schema) = schema {
+        schema.apply_defaults(&mut item);
     }
+    check_references(store, file_name, &item)?;
+
+    store.write(file_name, |arr| {
+        let existing = arr.iter_mut().find(|obj| id_matches(obj, key, id));
+        let id = match &existing {
+            Some(obj) => obj[key].clone(),
+            None if upsert => id_value(config, id)?,
+            None => return Err(AppError::NotFound),
+        };
+        if let Value::Object(map) = &mut item {
+            map.insert(key.to_string(), id);
+        }
+        touch(config, existing.as_deref(), &mut item);
+        if let Some(schema) = schema {
+            let violations = schema.violations(&item, "");
+            if !violations.is_empty() {
+                return Err(AppError::Invalid(violations));
+            }
+        }
+
+        match existing {
+            Some(obj) => *obj = item.clone(),
+            None => arr.push(item.clone()),
+        }
+        Ok(item)
+    })
 }
+
+// ! update where
+// merge patches every record matching `filter`, returning the updated records.
+// when one of them breaks the schema none is changed
+pub fn update_where(
+    store: &TableStore,
+    file_name: &str,
+    filter: &FilterType,
+    patch: &Value,
+) -> Result<Vec<Value>, AppError> {
+    let config = store.config(file_name);
+    let key = config.primary_key();
+    let schema = store.schema(file_name);
+    check_references(store, file_name, patch)?;
+    store.write(file_name, |arr| {
+        let mut updated: Vec<(usize, Value)> = vec![];
+        let mut violations = vec![];
+        for (position, obj) in arr.iter().enumerate() {
+            if !filter.evaluate(obj) {
+                continue;
+            }
+            let mut record = obj.clone();
+            json_patch::merge(&mut record, patch);
+            if let Some(id) = obj.get(key) {
+                record[key] = id.clone();
+            }
+            touch(config, Some(obj), &mut record);
+            if let Some(schema) = schema {
+                violations.extend(schema.violations(&record, &format!("/{}", updated.len())));
+            }
+            updated.push((position, record));
+        }
+        if !violations.is_empty() {
+            return Err(AppError::Invalid(violations));
+        }
+
+        for (position, record) in &updated {
+            arr[*position] = record.clone();
+        }
+        Ok(updated.into_iter().map(|(_, record)| record).collect())
+    })
+}
+
 // ! delete data
-pub fn delete_data(file_name: &str, id: u64) {
-    let mut data = read_json(file_name);
-    if let Value::Array(arr) = &mut data {
-        arr.retain(|obj| obj.get("id") != Some(&Value::Number(id.into())));
-        write_to_json(file_name, &data);
+// records pointing at the deleted one through a declared foreign key are
+// handled by its `on_delete`, see `helpers::integrity`. tables with
+// `soft_delete` only get `deleted_at` set
+pub fn delete_data(store: &TableStore, file_name: &str, id: &str) -> Result<(), AppError> {
+    if find_data(store, file_name, id).is_none() {
+        return Err(AppError::NotFound);
+    }
+    let plan = plan_delete(store, file_name, HashSet::from([id.to_string()]))?;
+    apply_delete(store, &plan);
+    Ok(())
+}
+
+// ! delete where
+// removes every record matching `filter`, returning the removed records. only
+// the records the delete was planned for go, so a record that starts matching
+// in between is left alone rather than deleted unchecked
+pub fn delete_where(
+    store: &TableStore,
+    file_name: &str,
+    filter: &FilterType,
+) -> Result<Vec<Value>, AppError> {
+    let config = store.config(file_name);
+    let key = config.primary_key();
+    let ids: HashSet<String> = store.read(file_name, |arr| {
+        arr.iter()
+            .filter(|obj| filter.evaluate(obj))
+            .filter_map(|obj| id_string(obj.get(key)))
+            .collect()
+    });
+    let plan = plan_delete(store, file_name, ids.clone())?;
+
+    let planned = |obj: &Value| id_string(obj.get(key)).is_some_and(|id| ids.contains(&id));
+    let deleted = store.write(file_name, |arr| {
+        if config.soft_delete() {
+            let mut deleted = vec![];
+            for obj in arr
+                .iter_mut()
+                .filter(|obj| planned(obj) && !is_deleted(config, obj))
+            {
+                mark_deleted(config, obj);
+                deleted.push(obj.clone());
+            }
+            return deleted;
+        }
+        let (deleted, kept): (Vec<Value>, Vec<Value>) = arr.drain(..).partition(planned);
+        *arr = kept;
+        deleted
+    });
+    apply_delete(store, &plan);
+    Ok(deleted)
+}
+
+// ! restore data
+// clears `deleted_at` of a soft deleted record. records deleted along with it
+// stay deleted, and a record pointing at a deleted one cannot come back
+pub fn restore_data(store: &TableStore, file_name: &str, id: &str) -> Result<Value, AppError> {
+    let config = store.config(file_name);
+    if !config.soft_delete() {
+        return Err(AppError::BadRequest(format!(
+            "{} does not use soft_delete",
+            file_name
+        )));
+    }
+    let record = find_data(store, file_name, id).ok_or(AppError::NotFound)?;
+    check_references(store, file_name, &record)?;
+    modify_record(store, file_name, id, |obj| {
+        if let Value::Object(map) = obj {
+            map.remove(DELETED_AT);
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs;
+    use std::sync::Arc;
+    use std::thread;
+
+    use serde_json::{Value, json};
+
+    use super::{
+        create_data, create_many, delete_data, delete_where, find_data, patch_data, replace_data,
+        restore_data, update_data, update_where,
+    };
+    use crate::error::AppError;
+    use crate::helpers::store::TableStore;
+    use crate::model::filter_type::FilterType;
+    use crate::model::toml_config::TableConfig;
+    use crate::storage::Storage;
+    use crate::storage::json::JsonStorage;
+    use crate::storage::memory::MemoryStorage;
+
+    #[test]
+    fn test_parallel_creates_keep_every_row() {
+        let table = "test_parallel_creates";
+        let dir = std::env::temp_dir().join(format!("aginisi_crud_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let store = Arc::new(TableStore::new(
+            Box::new(JsonStorage::new(&dir)),
+            Default::default(),
+        ));
+
+        let handles: Vec<_> = (0..8)
+            .map(|t| {
+                let store = store.clone();
+                thread::spawn(move || {
+                    for i in 0..25 {
+                        create_data(&store, table, json!({ "thread": t, "i": i })).unwrap();
+                        if i % 5 == 0 {
+                            store.flush();
+                        }
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        store.flush();
+
+        // what a restart would read back from disk
+        let rows = JsonStorage::new(&dir).load(table);
+        let mut ids: Vec<u64> = rows.iter().map(|row| row["id"].as_u64().unwrap()).collect();
+        ids.sort();
+        ids.dedup();
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(rows.len(), 200);
+        assert_eq!(ids.len(), 200);
+    }
+
+    #[test]
+    fn test_timestamps_soft_delete_and_restore() {
+        let table = "test_soft_delete";
+        let config = TableConfig {
+            timestamps: Some(true),
+            soft_delete: Some(true),
+            ..Default::default()
+        };
+        let store = TableStore::new(
+            Box::new(MemoryStorage::default()),
+            HashMap::from([(table.to_string(), config)]),
+        );
+
+        let created = create_data(&store, table, json!({ "name": "Ann" })).unwrap();
+        assert!(created["created_at"].is_string());
+        assert_eq!(created["created_at"], created["updated_at"]);
+        let imported = create_data(
+            &store,
+            table,
+            json!({ "name": "Ben", "created_at": "2024-01-01T00:00:00.000Z" }),
+        )
+        .unwrap();
+        assert_eq!(imported["updated_at"], "2024-01-01T00:00:00.000Z");
+
+        let updated = update_data(
+            &store,
+            table,
+            "2",
+            &json!({ "name": "Benny", "created_at": "2030-01-01T00:00:00.000Z" }),
+        )
+        .unwrap();
+        assert_eq!(updated["created_at"], "2024-01-01T00:00:00.000Z");
+        assert_ne!(updated["updated_at"], imported["updated_at"]);
+
+        delete_data(&store, table, "1").unwrap();
+        assert!(matches!(
+            delete_data(&store, table, "9"),
+            Err(AppError::NotFound)
+        ));
+        let deleted = find_data(&store, table, "1").unwrap();
+        assert!(deleted["deleted_at"].is_string());
+        let restored = restore_data(&store, table, "1").unwrap();
+        assert!(restored.get("deleted_at").is_none());
+        assert!(restore_data(&store, table, "9").is_err());
+    }
+
+    #[test]
+    fn test_upserted_auto_increment_keys_are_numbers() {
+        let store = TableStore::new(Box::new(MemoryStorage::default()), HashMap::new());
+        assert!(matches!(
+            replace_data(&store, "users", "abc", json!({ "name": "Ann" }), true),
+            Err(AppError::BadRequest(_))
+        ));
+        let created = replace_data(&store, "users", "7", json!({ "name": "Ann" }), true).unwrap();
+        assert_eq!(created["id"], 7);
+    }
+    #[test]
+    fn test_bulk_writes() {
+        let table = "test_bulk";
+        let store = TableStore::new(Box::new(MemoryStorage::default()), HashMap::new());
+        let items = vec![json!({ "role": "admin" }), json!({ "role": "member" })];
+        assert_eq!(create_many(&store, table, items).unwrap().len(), 2);
+
+        // a taken key fails the whole list
+        let items = vec![json!({ "id": 3 }), json!({ "id": 1 })];
+        assert!(matches!(
+            create_many(&store, table, items),
+            Err(AppError::Conflict(_))
+        ));
+        let items = vec![json!({ "id": 4 }), json!({ "id": 4 })];
+        assert!(matches!(
+            create_many(&store, table, items),
+            Err(AppError::Conflict(_))
+        ));
+        assert_eq!(store.read(table, |rows| rows.len()), 2);
+
+        create_data(&store, table, json!({ "role": "member" })).unwrap();
+        let members: FilterType =
+            serde_json::from_value(json!({ "type": "Equals", "key": "role", "value": "member" }))
+                .unwrap();
+        let updated = update_where(&store, table, &members, &json!({ "active": true })).unwrap();
+        assert_eq!(updated.len(), 2);
+        assert!(updated.iter().all(|row| row["active"] == true));
+        assert_eq!(find_data(&store, table, "1").unwrap().get("active"), None);
+
+        let deleted = delete_where(&store, table, &members).unwrap();
+        let ids: Vec<&Value> = deleted.iter().map(|row| &row["id"]).collect();
+        assert_eq!(ids, [&json!(2), &json!(3)]);
+        assert_eq!(
+            store.read(table, |rows| rows.clone()),
+            [json!({ "id": 1, "role": "admin" })]
+        );
+        assert!(delete_where(&store, table, &members).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_replace_and_json_patch() {
+        let table = "test_replace";
+        let store = TableStore::new(Box::new(MemoryStorage::default()), HashMap::new());
+        create_data(&store, table, json!({ "name": "Ann", "age": 30 })).unwrap();
+
+        let replaced = replace_data(&store, table, "1", json!({ "name": "Ben" }), false).unwrap();
+        assert_eq!(replaced, json!({ "id": 1, "name": "Ben" }));
+        assert!(matches!(
+            replace_data(&store, table, "2", json!({ "name": "Cat" }), false),
+            Err(AppError::NotFound)
+        ));
+        assert!(find_data(&store, table, "2").is_none());
+        replace_data(&store, table, "2", json!({ "name": "Cat" }), true).unwrap();
+        assert_eq!(find_data(&store, table, "2").unwrap()["name"], "Cat");
+
+        // every operation applies or none does
+        let operations = json!([
+            { "op": "replace", "path": "/name", "value": "Dan" },
+            { "op": "test", "path": "/name", "value": "Ben" }
+        ]);
+        assert!(matches!(
+            patch_data(&store, table, "1", operations),
+            Err(AppError::Conflict(_))
+        ));
+        assert_eq!(find_data(&store, table, "1").unwrap(), replaced);
+        let operations = json!([
+            { "op": "test", "path": "/name", "value": "Ben" },
+            { "op": "add", "path": "/tags", "value": ["a"] }
+        ]);
+        let patched = patch_data(&store, table, "1", operations).unwrap();
+        assert_eq!(patched, json!({ "id": 1, "name": "Ben", "tags": ["a"] }));
     }
 }
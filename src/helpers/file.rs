@@ -13,6 +13,6 @@ pub fn open_file(file_name: &str) -> File {
     File::open(format!("{}/{}.json", FOLDER_NAME, file_name)).unwrap()
 }
 
-pub fn delete_file(file_name: &str) -> () {
+pub fn delete_file(file_name: &str) {
     fs::remove_file(format!("{}/{}.json", FOLDER_NAME, file_name)).unwrap()
 }
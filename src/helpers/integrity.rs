@@ -0,0 +1,284 @@
+use std::collections::{HashMap, HashSet};
+
+use serde_json::Value;
+
+use crate::error::AppError;
+use crate::model::toml_config::{OnDelete, RelationKind};
+
+use super::crud::{id_string, is_deleted, mark_deleted};
+use super::store::TableStore;
+
+// ! [tables.posts.relations.user]
+// ! type = "belongs_to"
+// ! table = "users"
+// ! on_delete = "cascade"
+// declared belongs_to relations are foreign keys. a written key must match a
+// record of the related table, and deleting that record restricts, cascades to
+// or clears the records pointing at it. soft deleted records count as gone.
+// tables are only ever locked one at a time, so checks and cascades never wait
+// on each other
+
+// `field` of `table` records holds the key of a `target` record
+struct ForeignKey {
+    table: String,
+    field: String,
+    target: String,
+    on_delete: OnDelete,
+}
+
+fn foreign_keys<'a>(
+    store: &'a TableStore,
+    table: &'a str,
+) -> impl Iterator<Item = ForeignKey> + 'a {
+    store
+        .config(table)
+        .relations
+        .iter()
+        .filter(|(_, relation)| relation.kind == RelationKind::BelongsTo)
+        .map(move |(name, relation)| ForeignKey {
+            table: table.to_string(),
+            field: relation
+                .foreign_key
+                .clone()
+                .unwrap_or_else(|| format!("{}Id", name)),
+            target: relation.table.clone(),
+            on_delete: relation.on_delete.unwrap_or_default(),
+        })
+}
+
+/// Fails with 409 when a foreign key in `record` does not match a record of
+/// its table. Missing and null keys are fine.
+pub fn check_references(store: &TableStore, table: &str, record: &Value) -> Result<(), AppError> {
+    for foreign_key in foreign_keys(store, table) {
+        let Some(id) = id_string(record.get(&foreign_key.field)) else {
+            continue;
+        };
+        let config = store.config(&foreign_key.target);
+        let key = config.primary_key();
+        let exists = store.read(&foreign_key.target, |rows| {
+            rows.iter().any(|row| {
+                id_string(row.get(key)).is_some_and(|v| v == id) && !is_deleted(config, row)
+            })
+        });
+        if !exists {
+            return Err(AppError::Conflict(format!(
+                "{} {} does not match any record of {}",
+                foreign_key.field, id, foreign_key.target
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// What deleting some records takes: the records to remove from every table,
+/// the deleted ones included, and the keys to clear.
+#[derive(Debug, Default)]
+pub struct DeletePlan {
+    deletes: HashMap<String, HashSet<String>>,
+    clears: Vec<(String, String, HashSet<String>)>,
+}
+
+/// Plans the delete of the `ids` records of `table`, following cascades. Fails
+/// with 409 when a restricting record still points at one of them.
+pub fn plan_delete(
+    store: &TableStore,
+    table: &str,
+    ids: HashSet<String>,
+) -> Result<DeletePlan, AppError> {
+    plan_deletes(store, HashMap::from([(table.to_string(), ids)]))
+}
+
+/// Plans the delete of records of several tables at once, records pointing at
+/// each other across them do not restrict.
+pub fn plan_deletes(
+    store: &TableStore,
+    targets: HashMap<String, HashSet<String>>,
+) -> Result<DeletePlan, AppError> {
+    let mut plan = DeletePlan::default();
+    for (table, ids) in &targets {
+        plan.deletes
+            .entry(table.clone())
+            .or_default()
+            .extend(ids.iter().cloned());
+    }
+    for (table, ids) in &targets {
+        follow(store, table, ids, &mut plan)?;
+    }
+    Ok(plan)
+}
+
+fn follow(
+    store: &TableStore,
+    table: &str,
+    ids: &HashSet<String>,
+    plan: &mut DeletePlan,
+) -> Result<(), AppError> {
+    let names = store.names();
+    let referencing: Vec<ForeignKey> = store
+        .configs()
+        .keys()
+        .filter(|child| names.contains(child))
+        .flat_map(|child| foreign_keys(store, child))
+        .filter(|foreign_key| foreign_key.target == table)
+        .collect();
+
+    for foreign_key in referencing {
+        let config = store.config(&foreign_key.table);
+        let key = config.primary_key();
+        let deleting = plan.deletes.get(&foreign_key.table);
+        // the keys of the records pointing at `ids` that are not going anyway
+        let pointing: Vec<Option<String>> = store.read(&foreign_key.table, |rows| {
+            rows.iter()
+                .filter(|row| !is_deleted(config, row))
+                .filter(|row| {
+                    id_string(row.get(&foreign_key.field)).is_some_and(|id| ids.contains(&id))
+                })
+                .map(|row| id_string(row.get(key)))
+                .filter(|id| {
+                    !id.as_ref()
+                        .is_some_and(|id| deleting.is_some_and(|deleting| deleting.contains(id)))
+                })
+                .collect()
+        });
+        if pointing.is_empty() {
+            continue;
+        }
+
+        match foreign_key.on_delete {
+            OnDelete::Restrict => {
+                return Err(AppError::Conflict(format!(
+                    "{} record is still referenced by {}.{}",
+                    table, foreign_key.table, foreign_key.field
+                )));
+            }
+            OnDelete::Cascade => {
+                let cascaded: HashSet<String> = pointing.into_iter().flatten().collect();
+                plan.deletes
+                    .entry(foreign_key.table.clone())
+                    .or_default()
+                    .extend(cascaded.iter().cloned());
+                follow(store, &foreign_key.table, &cascaded, plan)?;
+            }
+            OnDelete::SetNull => {
+                plan.clears
+                    .push((foreign_key.table, foreign_key.field, ids.clone()));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Carries out `plan`, one table at a time. Records of `soft_delete` tables
+/// are marked deleted instead of removed.
+pub fn apply_delete(store: &TableStore, plan: &DeletePlan) {
+    for (table, ids) in &plan.deletes {
+        let config = store.config(table);
+        let key = config.primary_key();
+        let planned = |row: &Value| id_string(row.get(key)).is_some_and(|id| ids.contains(&id));
+        store.write(table, |rows| {
+            if config.soft_delete() {
+                rows.iter_mut()
+                    .filter(|row| planned(row))
+                    .for_each(|row| mark_deleted(config, row));
+            } else {
+                rows.retain(|row| !planned(row));
+            }
+        });
+    }
+    for (table, field, ids) in &plan.clears {
+        store.write(table, |rows| {
+            for row in rows.iter_mut() {
+                if id_string(row.get(field)).is_some_and(|id| ids.contains(&id)) {
+                    row[field.as_str()] = Value::Null;
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use serde_json::{Value, json};
+
+    use super::{apply_delete, check_references, plan_delete};
+    use crate::helpers::store::TableStore;
+    use crate::model::toml_config::TableConfig;
+    use crate::storage::memory::MemoryStorage;
+
+    fn store() -> TableStore {
+        let tables = HashMap::from([
+            (
+                "users".to_string(),
+                vec![json!({ "id": 1 }), json!({ "id": 2 })],
+            ),
+            (
+                "posts".to_string(),
+                vec![
+                    json!({ "id": 1, "userId": 1 }),
+                    json!({ "id": 2, "userId": 2 }),
+                ],
+            ),
+            (
+                "comments".to_string(),
+                vec![
+                    json!({ "id": 1, "postId": 1, "userId": 2 }),
+                    json!({ "id": 2, "postId": 2, "userId": 1 }),
+                ],
+            ),
+        ]);
+        let configs = HashMap::from([
+            (
+                "posts".to_string(),
+                TableConfig::from_toml(
+                    "[relations.user]\ntype = \"belongs_to\"\ntable = \"users\"\non_delete = \"cascade\"",
+                ),
+            ),
+            (
+                "comments".to_string(),
+                TableConfig::from_toml(
+                    "[relations.post]\ntype = \"belongs_to\"\ntable = \"posts\"\non_delete = \"cascade\"\n\
+                     [relations.user]\ntype = \"belongs_to\"\ntable = \"users\"\non_delete = \"set_null\"",
+                ),
+            ),
+        ]);
+        TableStore::new(Box::new(MemoryStorage::new(tables)), configs)
+    }
+
+    fn ids(store: &TableStore, table: &str) -> Vec<Value> {
+        store.read(table, |rows| {
+            rows.iter().map(|row| row["id"].clone()).collect()
+        })
+    }
+
+    #[test]
+    fn test_references_and_delete_rules() {
+        let store = store();
+        assert!(check_references(&store, "posts", &json!({ "userId": 2 })).is_ok());
+        assert!(check_references(&store, "posts", &json!({ "userId": null })).is_ok());
+        assert!(check_references(&store, "posts", &json!({ "userId": 9 })).is_err());
+
+        // user 1 takes post 1 and its comment along, comment 2 loses its user
+        let plan = plan_delete(&store, "users", HashSet::from(["1".to_string()])).unwrap();
+        apply_delete(&store, &plan);
+        assert_eq!(ids(&store, "users"), [json!(2)]);
+        assert_eq!(ids(&store, "posts"), [json!(2)]);
+        assert_eq!(
+            store.read("comments", |rows| rows.clone()),
+            [json!({ "id": 2, "postId": 2, "userId": null })]
+        );
+
+        // a restricting relation blocks the delete
+        let config =
+            TableConfig::from_toml("[relations.user]\ntype = \"belongs_to\"\ntable = \"users\"");
+        let store = TableStore::new(
+            Box::new(MemoryStorage::new(HashMap::from([
+                ("users".to_string(), vec![json!({ "id": 1 })]),
+                ("posts".to_string(), vec![json!({ "id": 1, "userId": 1 })]),
+            ]))),
+            HashMap::from([("posts".to_string(), config)]),
+        );
+        assert!(plan_delete(&store, "users", HashSet::from(["1".to_string()])).is_err());
+    }
+}
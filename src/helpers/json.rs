@@ -1,15 +1,15 @@
 use serde_json::{Value, json};
-use std::fs::{self};
-use std::path::Path as FilePath;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
 
 use crate::consts::FOLDER_NAME;
 
-use super::file::create_file;
-
-pub fn read_json(file_name: &str) -> Value {
-    let path = format!("{}/{}.json", FOLDER_NAME, file_name);
-    if !FilePath::new(&path).exists() {
-        create_file(file_name);
+// reading a table that does not exist yet creates its empty file
+pub fn read_json(dir: &Path, file_name: &str) -> Value {
+    let path = dir.join(format!("{}.json", file_name));
+    if !path.exists() {
+        File::create(&path).ok();
         return json!([]); // Default to empty array
     }
     let data = fs::read_to_string(path).unwrap_or_else(|_| "[]".to_string());
@@ -18,5 +18,22 @@ pub fn read_json(file_name: &str) -> Value {
 
 pub fn write_to_json(file_name: &str, data: &Value) {
     let json = serde_json::to_string_pretty(data).unwrap();
-    fs::write(format!("{}/{}.json", FOLDER_NAME, file_name), json).unwrap()
+    write_table_file(Path::new(FOLDER_NAME), file_name, &json)
+}
+
+// writes to a temp file, syncs it and renames it over the table so a crash
+// mid-write leaves either the old or the new table, never a truncated one
+pub fn write_table_file(dir: &Path, file_name: &str, json: &str) {
+    let path = dir.join(format!("{}.json", file_name));
+    let tmp_path = dir.join(format!(".{}.json.tmp", file_name));
+
+    let mut file = File::create(&tmp_path).unwrap();
+    file.write_all(json.as_bytes()).unwrap();
+    file.sync_all().unwrap();
+    fs::rename(&tmp_path, &path).unwrap();
+
+    // persist the rename itself; not every platform can open a directory
+    if let Ok(dir) = File::open(dir) {
+        dir.sync_all().ok();
+    }
 }
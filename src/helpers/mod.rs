@@ -1,4 +1,9 @@
 pub mod crud;
 pub mod file;
+pub mod integrity;
 pub mod json;
+pub mod relations;
+pub mod schema;
+pub mod seed;
+pub mod store;
 pub mod toml;
@@ -0,0 +1,386 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::error::AppError;
+use crate::model::toml_config::RelationKind;
+
+use super::crud::{id_string, is_deleted};
+use super::store::TableStore;
+
+// ! ?_expand=user&_embed=comments.user
+// attaches related records under the relation name. relations come from
+// `[tables.x.relations]` or from naming conventions:
+// - `user` on posts with a `userId` (or `user_id`) field is the matching `users` record
+// - `tags` on posts with a `posts_tags` (or `tags_posts`) table are the tags paired there
+// - `comments` on posts are the comments whose `postId` (or `post_id`) is the post's key
+// a dotted path includes the relations of the included records as well
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Include {
+    name: String,
+    children: Vec<Include>,
+}
+
+// every level of a path is a recursive `include_related` call
+const MAX_DEPTH: usize = 16;
+
+/// Builds the include tree from comma separated, dotted relation paths.
+pub fn parse_includes<'a>(
+    specs: impl IntoIterator<Item = &'a str>,
+) -> Result<Vec<Include>, AppError> {
+    let mut includes: Vec<Include> = vec![];
+    for path in specs.into_iter().flat_map(|spec| spec.split(',')) {
+        let names: Vec<&str> = path
+            .split('.')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .collect();
+        if names.len() > MAX_DEPTH {
+            return Err(AppError::BadRequest(format!(
+                "Relation path nested deeper than {} levels",
+                MAX_DEPTH
+            )));
+        }
+        let mut level = &mut includes;
+        for name in names {
+            let index = match level.iter().position(|include| include.name == name) {
+                Some(index) => index,
+                None => {
+                    level.push(Include {
+                        name: name.to_string(),
+                        children: vec![],
+                    });
+                    level.len() - 1
+                }
+            };
+            level = &mut level[index].children;
+        }
+    }
+    Ok(includes)
+}
+
+struct Resolved {
+    kind: RelationKind,
+    table: String,
+    foreign_key: String,
+    through: Option<String>,
+    other_key: String,
+}
+
+fn singular(table: &str) -> &str {
+    table.strip_suffix('s').unwrap_or(table)
+}
+
+// `{name}Id` when some row of `table` uses it, `{name}_id` when only that is used
+fn key_for(store: &TableStore, table: &str, rows: Option<&[Value]>, name: &str) -> String {
+    let camel = format!("{}Id", name);
+    let snake = format!("{}_id", name);
+    let uses = |rows: &[Value], key: &str| rows.iter().any(|row| row.get(key).is_some());
+    let snake_only = match rows {
+        Some(rows) => !uses(rows, &camel) && uses(rows, &snake),
+        None => store.read(table, |rows| !uses(rows, &camel) && uses(rows, &snake)),
+    };
+    if snake_only { snake } else { camel }
+}
+
+fn resolve(
+    store: &TableStore,
+    table: &str,
+    rows: &[Value],
+    name: &str,
+    names: &[String],
+) -> Result<Resolved, AppError> {
+    let exists = |table: &str| names.iter().any(|n| n == table);
+
+    if let Some(relation) = store.config(table).relations.get(name) {
+        if relation.kind == RelationKind::ManyToMany && relation.through.is_none() {
+            return Err(AppError::BadRequest(format!(
+                "Relation {} on {} needs a through table",
+                name, table
+            )));
+        }
+        let foreign_key = relation
+            .foreign_key
+            .clone()
+            .unwrap_or_else(|| match relation.kind {
+                RelationKind::BelongsTo => format!("{}Id", name),
+                _ => format!("{}Id", singular(table)),
+            });
+        return Ok(Resolved {
+            kind: relation.kind,
+            table: relation.table.clone(),
+            foreign_key,
+            through: relation.through.clone(),
+            other_key: relation
+                .other_key
+                .clone()
+                .unwrap_or_else(|| format!("{}Id", singular(&relation.table))),
+        });
+    }
+
+    let unknown = || AppError::BadRequest(format!("Unknown relation {} on {}", name, table));
+    let belongs_to = key_for(store, table, Some(rows), name);
+    if rows.iter().any(|row| row.get(&belongs_to).is_some()) {
+        let target = [format!("{}s", name), name.to_string()]
+            .into_iter()
+            .find(|t| exists(t))
+            .ok_or_else(unknown)?;
+        return Ok(Resolved {
+            kind: RelationKind::BelongsTo,
+            table: target,
+            foreign_key: belongs_to,
+            through: None,
+            other_key: String::new(),
+        });
+    }
+    if !exists(name) {
+        return Err(unknown());
+    }
+    for join in [format!("{}_{}", table, name), format!("{}_{}", name, table)] {
+        if exists(&join) {
+            return Ok(Resolved {
+                kind: RelationKind::ManyToMany,
+                table: name.to_string(),
+                foreign_key: key_for(store, &join, None, singular(table)),
+                other_key: key_for(store, &join, None, singular(name)),
+                through: Some(join),
+            });
+        }
+    }
+    Ok(Resolved {
+        kind: RelationKind::HasMany,
+        table: name.to_string(),
+        foreign_key: key_for(store, name, None, singular(table)),
+        through: None,
+        other_key: String::new(),
+    })
+}
+
+/// The field of `child` records that holds the key of their `parent` record,
+/// declared on either table or `{parent singular}Id` by convention.
+pub fn child_key(store: &TableStore, parent: &str, child: &str) -> String {
+    let has_many = store
+        .config(parent)
+        .relations
+        .values()
+        .find(|relation| relation.kind == RelationKind::HasMany && relation.table == child);
+    if let Some(relation) = has_many {
+        return relation
+            .foreign_key
+            .clone()
+            .unwrap_or_else(|| format!("{}Id", singular(parent)));
+    }
+    let belongs_to = store
+        .config(child)
+        .relations
+        .values()
+        .find(|relation| relation.kind == RelationKind::BelongsTo && relation.table == parent);
+    if let Some(foreign_key) = belongs_to.and_then(|relation| relation.foreign_key.clone()) {
+        return foreign_key;
+    }
+
+    // a table that does not exist yet is not loaded just to look at its fields
+    let rows: Option<&[Value]> = match store.names().iter().any(|name| name == child) {
+        true => None,
+        false => Some(&[]),
+    };
+    key_for(store, child, rows, singular(parent))
+}
+
+/// Attaches the records of every relation in `includes` to `rows` of `table`.
+pub fn include_related(
+    store: &TableStore,
+    table: &str,
+    rows: &mut [Value],
+    includes: &[Include],
+) -> Result<(), AppError> {
+    if includes.is_empty() || rows.is_empty() {
+        return Ok(());
+    }
+    let names = store.names();
+    let key = store.config(table).primary_key().to_string();
+
+    for include in includes {
+        let relation = resolve(store, table, rows, &include.name, &names)?;
+        let target_key = store.config(&relation.table).primary_key().to_string();
+        let own_ids = |rows: &[Value]| -> Vec<Option<String>> {
+            rows.iter().map(|row| id_string(row.get(&key))).collect()
+        };
+
+        // the related records of every row, by position
+        let mut related: Vec<Vec<Value>> = match relation.kind {
+            RelationKind::BelongsTo => {
+                let wanted: Vec<Option<String>> = rows
+                    .iter()
+                    .map(|row| id_string(row.get(&relation.foreign_key)))
+                    .collect();
+                let by_id = records_by(store, &relation.table, &target_key);
+                wanted
+                    .iter()
+                    .map(|id| {
+                        id.as_ref()
+                            .and_then(|id| by_id.get(id))
+                            .cloned()
+                            .unwrap_or_default()
+                    })
+                    .collect()
+            }
+            RelationKind::HasMany => {
+                let by_owner = records_by(store, &relation.table, &relation.foreign_key);
+                own_ids(rows)
+                    .iter()
+                    .map(|id| {
+                        id.as_ref()
+                            .and_then(|id| by_owner.get(id))
+                            .cloned()
+                            .unwrap_or_default()
+                    })
+                    .collect()
+            }
+            RelationKind::ManyToMany => {
+                let through = relation.through.as_deref().unwrap_or_default();
+                let pairs = records_by(store, through, &relation.foreign_key);
+                let by_id = records_by(store, &relation.table, &target_key);
+                own_ids(rows)
+                    .iter()
+                    .map(|id| {
+                        id.as_ref()
+                            .and_then(|id| pairs.get(id))
+                            .into_iter()
+                            .flatten()
+                            .filter_map(|pair| id_string(pair.get(&relation.other_key)))
+                            .filter_map(|other| by_id.get(&other))
+                            .flatten()
+                            .cloned()
+                            .collect()
+                    })
+                    .collect()
+            }
+        };
+
+        for records in related.iter_mut() {
+            include_related(store, &relation.table, records, &include.children)?;
+        }
+        for (row, records) in rows.iter_mut().zip(related) {
+            let Value::Object(obj) = row else {
+                continue;
+            };
+            let value = match relation.kind {
+                RelationKind::BelongsTo => records.into_iter().next().unwrap_or(Value::Null),
+                _ => Value::Array(records),
+            };
+            obj.insert(include.name.clone(), value);
+        }
+    }
+    Ok(())
+}
+
+// the records of `table` grouped by how their `field` prints, soft deleted ones left out
+fn records_by(store: &TableStore, table: &str, field: &str) -> HashMap<String, Vec<Value>> {
+    let config = store.config(table);
+    store.read(table, |rows| {
+        let mut grouped: HashMap<String, Vec<Value>> = HashMap::new();
+        for row in rows.iter().filter(|row| !is_deleted(config, row)) {
+            if let Some(id) = id_string(row.get(field)) {
+                grouped.entry(id).or_default().push(row.clone());
+            }
+        }
+        grouped
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    use super::{include_related, parse_includes};
+    use crate::helpers::store::TableStore;
+    use crate::model::toml_config::TableConfig;
+    use crate::storage::memory::MemoryStorage;
+
+    #[test]
+    fn test_expand_embed_and_join_tables() {
+        let tables = HashMap::from([
+            (
+                "users".to_string(),
+                vec![
+                    json!({ "id": 1, "name": "Ann" }),
+                    json!({ "id": 2, "name": "Ben" }),
+                ],
+            ),
+            (
+                "posts".to_string(),
+                vec![
+                    json!({ "id": 1, "userId": 1 }),
+                    json!({ "id": 2, "userId": 2 }),
+                ],
+            ),
+            (
+                "comments".to_string(),
+                vec![
+                    json!({ "id": 1, "postId": 1, "userId": 2 }),
+                    json!({ "id": 2, "postId": 1, "userId": 1 }),
+                ],
+            ),
+            (
+                "tags".to_string(),
+                vec![json!({ "id": "a" }), json!({ "id": "b" })],
+            ),
+            (
+                "posts_tags".to_string(),
+                vec![
+                    json!({ "postId": 1, "tagId": "b" }),
+                    json!({ "postId": 1, "tagId": "a" }),
+                ],
+            ),
+        ]);
+        let store = TableStore::new(Box::new(MemoryStorage::new(tables.clone())), HashMap::new());
+
+        let mut posts = tables["posts"].clone();
+        let includes = parse_includes(["user", "comments.user,tags"]).unwrap();
+        include_related(&store, "posts", &mut posts, &includes).unwrap();
+
+        assert_eq!(posts[0]["user"]["name"], "Ann");
+        assert_eq!(posts[0]["comments"][0]["user"]["name"], "Ben");
+        assert_eq!(posts[0]["tags"], json!([{ "id": "b" }, { "id": "a" }]));
+        assert_eq!(posts[1]["comments"], json!([]));
+        assert!(
+            include_related(
+                &store,
+                "posts",
+                &mut posts,
+                &parse_includes(["nope"]).unwrap()
+            )
+            .is_err()
+        );
+
+        // a declared relation with its own key
+        let mut configs = HashMap::new();
+        let config = TableConfig::from_toml(
+            "[relations.writer]\ntype = \"belongs_to\"\ntable = \"users\"\nforeign_key = \"userId\"",
+        );
+        configs.insert("posts".to_string(), config);
+        let store = TableStore::new(Box::new(MemoryStorage::new(tables.clone())), configs);
+        let mut posts = tables["posts"].clone();
+        include_related(
+            &store,
+            "posts",
+            &mut posts,
+            &parse_includes(["writer"]).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(posts[1]["writer"]["name"], "Ben");
+    }
+
+    #[test]
+    fn test_include_depth_is_limited() {
+        let path = |depth: usize| vec!["manager"; depth].join(".");
+        let includes = parse_includes([path(16).as_str()]).unwrap();
+        assert_eq!(includes.len(), 1);
+        assert!(parse_includes([path(17).as_str()]).is_err());
+        assert!(parse_includes(["user", path(800).as_str()]).is_err());
+    }
+}
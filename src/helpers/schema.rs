@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::fs;
+
+use jsonschema::Validator;
+use serde_json::{Value, json};
+
+use crate::consts::{FOLDER_NAME, SCHEMA_FOLDER_NAME};
+use crate::model::toml_config::TableConfig;
+
+// ! aginisi/_schemas/users.schema.json
+// ! [tables.users.schema]
+// ! required = ["email"]
+// a JSON Schema per table, inline in the config or in its own file (the config
+// wins). records written whole get the schema's defaults, then every write is
+// checked against it and refused with the full list of violations
+
+pub struct TableSchema {
+    schema: Value,
+    validator: Validator,
+}
+
+impl TableSchema {
+    pub fn new(schema: Value) -> Result<TableSchema, String> {
+        let validator = jsonschema::options()
+            .should_validate_formats(true)
+            .build(&schema)
+            .map_err(|e| e.to_string())?;
+        Ok(TableSchema { schema, validator })
+    }
+
+    pub fn document(&self) -> &Value {
+        &self.schema
+    }
+
+    /// Adds the `default` of every property missing from `record`, nested
+    /// objects included.
+    pub fn apply_defaults(&self, record: &mut Value) {
+        fill_defaults(&self.schema, record);
+    }
+
+    /// Every violation in `record` as `{pointer, message}`, `prefix` goes in
+    /// front of the pointers.
+    pub fn violations(&self, record: &Value, prefix: &str) -> Vec<Value> {
+        self.validator
+            .iter_errors(record)
+            .map(|error| {
+                json!({
+                    "pointer": format!("{}{}", prefix, error.instance_path()),
+                    "message": error.to_string(),
+                })
+            })
+            .collect()
+    }
+}
+
+fn fill_defaults(schema: &Value, value: &mut Value) {
+    let (Some(properties), Value::Object(obj)) =
+        (schema.get("properties").and_then(Value::as_object), value)
+    else {
+        return;
+    };
+    for (name, property) in properties {
+        match obj.get_mut(name) {
+            Some(inner) => fill_defaults(property, inner),
+            None => {
+                if let Some(default) = property.get("default") {
+                    obj.insert(name.clone(), default.clone());
+                }
+            }
+        }
+    }
+}
+
+/// The schema of every table that has one, from the config or from
+/// `aginisi/_schemas/{table}.schema.json`.
+pub fn load_schemas(
+    configs: &HashMap<String, TableConfig>,
+) -> Result<HashMap<String, TableSchema>, String> {
+    let mut documents: HashMap<String, Value> = HashMap::new();
+    let folder = format!("{}/{}", FOLDER_NAME, SCHEMA_FOLDER_NAME);
+    if let Ok(entries) = fs::read_dir(&folder) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(table) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".schema.json"))
+            else {
+                continue;
+            };
+            let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+            let document =
+                serde_json::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))?;
+            documents.insert(table.to_string(), document);
+        }
+    }
+    for (table, config) in configs {
+        if let Some(schema) = &config.schema {
+            documents.insert(table.clone(), schema.clone());
+        }
+    }
+
+    documents
+        .into_iter()
+        .map(|(table, document)| {
+            let schema = TableSchema::new(document)
+                .map_err(|e| format!("Invalid schema for {}: {}", table, e))?;
+            Ok((table, schema))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::TableSchema;
+
+    #[test]
+    fn test_defaults_and_violations() {
+        let schema = TableSchema::new(json!({
+            "type": "object",
+            "required": ["email"],
+            "properties": {
+                "email": { "type": "string", "format": "email" },
+                "age": { "type": "integer", "minimum": 0 },
+                "role": { "type": "string", "default": "member" },
+                "address": {
+                    "type": "object",
+                    "properties": { "country": { "type": "string", "default": "NG" } }
+                }
+            }
+        }))
+        .unwrap();
+
+        let mut record = json!({ "email": "ann@example.com", "address": {} });
+        schema.apply_defaults(&mut record);
+        assert_eq!(record["role"], "member");
+        assert_eq!(record["address"]["country"], "NG");
+        assert!(schema.violations(&record, "").is_empty());
+
+        let pointers: Vec<_> = schema
+            .violations(&json!({ "email": "nope", "age": -1 }), "/3")
+            .into_iter()
+            .map(|violation| violation["pointer"].clone())
+            .collect();
+        assert_eq!(pointers.len(), 2);
+        assert!(pointers.contains(&json!("/3/email")));
+        assert!(pointers.contains(&json!("/3/age")));
+        assert!(TableSchema::new(json!({ "type": 5 })).is_err());
+    }
+}
@@ -0,0 +1,790 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::str::FromStr;
+
+use chrono::DateTime;
+use fake::Fake;
+use fake::faker::address::en::{
+    BuildingNumber, CityName, CountryName, StateName, StreetName, ZipCode,
+};
+use fake::faker::company::en::CompanyName;
+use fake::faker::internet::en::{SafeEmail, Username};
+use fake::faker::job::en::Title as JobTitle;
+use fake::faker::lorem::en::{Paragraph, Paragraphs, Sentence, Word, Words};
+use fake::faker::name::en::{FirstName, LastName, Name};
+use fake::faker::phone_number::en::PhoneNumber;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde_json::{Map, Value, json};
+use ulid::Ulid;
+use uuid::Builder;
+
+use crate::error::AppError;
+use crate::model::seed::SeedInput;
+use crate::model::toml_config::{IdStrategy, RelationKind};
+
+use super::crud::{create_many, id_string, is_deleted};
+use super::integrity::{apply_delete, plan_deletes};
+use super::store::TableStore;
+
+// ! aginisi seed users:50 posts:200 --seed 7
+// ! POST /_admin/seed {"tables": ["users:50", "posts:200"], "seed": 7}
+// makes up records for tables. a field's value comes from, by preference, its
+// `[tables.x.fake]` hint, a declared belongs_to relation, the table's schema,
+// or the fields of the records already there guessed from their names. every
+// value is drawn from one generator, so a seed always gives the same records
+
+// made up dates fall between 2020-01-01 and 2026-01-01
+const DATE_RANGE: (i64, i64) = (1_577_836_800, 1_767_225_600);
+// the share of `field = "hint?"` values left null
+const NULL_RATE: f64 = 0.2;
+// the most elements a made up list gets, whatever the schema's `maxItems`
+const MAX_ITEMS: usize = 100;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Hint {
+    Name,
+    FirstName,
+    LastName,
+    Username,
+    Email,
+    Phone,
+    Company,
+    Job,
+    Street,
+    City,
+    State,
+    Zip,
+    Country,
+    Address,
+    Latitude,
+    Longitude,
+    Word,
+    Words,
+    Sentence,
+    Paragraph,
+    LongText,
+    Date,
+    DateTime,
+    Image,
+    Url,
+    Uuid,
+    Bool,
+    Int(i64, i64),
+    Float(f64, f64),
+    Price,
+    Enum(Vec<String>),
+    Ref(String),
+    Sample(Value),
+    Schema(Value),
+    Optional(Box<Hint>),
+}
+
+fn range<T: FromStr>(arg: Option<&str>) -> Option<(T, T)> {
+    let (from, to) = arg?.split_once("..")?;
+    Some((from.trim().parse().ok()?, to.trim().parse().ok()?))
+}
+
+impl Hint {
+    // `name`, `int:1..5`, `float:0..1`, `enum:a,b`, `ref:users`, `sentence?`
+    fn parse(spec: &str) -> Option<Hint> {
+        let spec = spec.trim();
+        if let Some(inner) = spec.strip_suffix('?') {
+            return Some(Hint::Optional(Box::new(Hint::parse(inner)?)));
+        }
+        let (kind, arg) = match spec.split_once(':') {
+            Some((kind, arg)) => (kind.trim(), Some(arg)),
+            None => (spec, None),
+        };
+        let hint = match (kind, arg) {
+            ("int", None) => Hint::Int(0, 1000),
+            ("int", arg) => {
+                let (from, to) = range(arg)?;
+                Hint::Int(from, to)
+            }
+            ("float", None) => Hint::Float(0.0, 1000.0),
+            ("float", arg) => {
+                let (from, to): (f64, f64) = range(arg)?;
+                if !from.is_finite() || !to.is_finite() {
+                    return None;
+                }
+                Hint::Float(from, to)
+            }
+            ("enum", Some(values)) => {
+                Hint::Enum(values.split(',').map(|v| v.trim().to_string()).collect())
+            }
+            ("ref", Some(table)) => Hint::Ref(table.trim().to_string()),
+            (_, Some(_)) => return None,
+            ("name", None) => Hint::Name,
+            ("first_name", None) => Hint::FirstName,
+            ("last_name", None) => Hint::LastName,
+            ("username", None) => Hint::Username,
+            ("email", None) => Hint::Email,
+            ("phone", None) => Hint::Phone,
+            ("company", None) => Hint::Company,
+            ("job", None) => Hint::Job,
+            ("street", None) => Hint::Street,
+            ("city", None) => Hint::City,
+            ("state", None) => Hint::State,
+            ("zip", None) => Hint::Zip,
+            ("country", None) => Hint::Country,
+            ("address", None) => Hint::Address,
+            ("latitude", None) => Hint::Latitude,
+            ("longitude", None) => Hint::Longitude,
+            ("word", None) => Hint::Word,
+            ("words", None) => Hint::Words,
+            ("sentence", None) => Hint::Sentence,
+            ("paragraph", None) => Hint::Paragraph,
+            ("long_text", None) => Hint::LongText,
+            ("date", None) => Hint::Date,
+            ("datetime", None) => Hint::DateTime,
+            ("image", None) => Hint::Image,
+            ("url", None) => Hint::Url,
+            ("uuid", None) => Hint::Uuid,
+            ("bool", None) => Hint::Bool,
+            ("price", None) => Hint::Price,
+            _ => return None,
+        };
+        Some(hint)
+    }
+
+    // a guess from the field name alone, `None` when the name says nothing
+    fn for_field(field: &str) -> Option<Hint> {
+        let name: String = field
+            .chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect();
+        let is = |names: &[&str]| names.contains(&name.as_str());
+        let has = |words: &[&str]| words.iter().any(|word| name.contains(word));
+        let flag = ["is", "has"].iter().any(|prefix| {
+            field
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.starts_with(|c: char| c.is_uppercase() || c == '_'))
+        });
+
+        let hint = if has(&["email"]) {
+            Hint::Email
+        } else if is(&["firstname", "givenname"]) {
+            Hint::FirstName
+        } else if is(&["lastname", "surname", "familyname"]) {
+            Hint::LastName
+        } else if is(&["username", "login", "handle"]) {
+            Hint::Username
+        } else if is(&["name", "fullname", "displayname", "author"]) {
+            Hint::Name
+        } else if has(&["phone"]) || is(&["mobile", "tel"]) {
+            Hint::Phone
+        } else if is(&["company", "organization", "organisation"]) {
+            Hint::Company
+        } else if is(&["job", "jobtitle", "position", "occupation"]) {
+            Hint::Job
+        } else if is(&["street", "streetaddress", "address1"]) {
+            Hint::Street
+        } else if is(&["address"]) {
+            Hint::Address
+        } else if name.ends_with("city") {
+            Hint::City
+        } else if is(&["state", "region", "province"]) {
+            Hint::State
+        } else if is(&["zip", "zipcode", "postcode", "postalcode"]) {
+            Hint::Zip
+        } else if name.ends_with("country") {
+            Hint::Country
+        } else if is(&["lat", "latitude"]) {
+            Hint::Latitude
+        } else if is(&["lng", "lon", "longitude"]) {
+            Hint::Longitude
+        } else if has(&["image", "avatar", "photo", "picture", "thumbnail"]) {
+            Hint::Image
+        } else if name.ends_with("url") || is(&["website", "link", "homepage"]) {
+            Hint::Url
+        } else if is(&["uuid", "guid"]) {
+            Hint::Uuid
+        } else if is(&["title", "subject", "headline"]) {
+            Hint::Sentence
+        } else if is(&["description", "bio", "summary", "about", "comment"]) {
+            Hint::Paragraph
+        } else if is(&["body", "content", "text", "notes"]) {
+            Hint::LongText
+        } else if is(&["price", "amount", "total", "cost", "balance"]) {
+            Hint::Price
+        } else if is(&["age"]) {
+            Hint::Int(18, 90)
+        } else if is(&["rating", "stars"]) {
+            Hint::Int(1, 5)
+        } else if is(&["quantity", "qty", "stock"]) {
+            Hint::Int(0, 100)
+        } else if flag || is(&["active", "enabled", "published", "verified", "done"]) {
+            Hint::Bool
+        } else if is(&["birthday", "birthdate", "dob"]) || name.ends_with("date") {
+            Hint::Date
+        } else if field.ends_with("At") || field.ends_with("_at") {
+            Hint::DateTime
+        } else {
+            return None;
+        };
+        Some(hint)
+    }
+
+    // a guess from a value the field already holds
+    fn for_sample(field: &str, sample: &Value) -> Hint {
+        if let Some(hint) = Hint::for_field(field) {
+            return hint;
+        }
+        match sample {
+            Value::Bool(_) => Hint::Bool,
+            Value::Number(n) if n.is_f64() => Hint::Float(0.0, 1000.0),
+            Value::Number(_) => Hint::Int(0, 1000),
+            Value::String(_) => Hint::Words,
+            other => Hint::Sample(other.clone()),
+        }
+    }
+}
+
+struct Generator<'a> {
+    store: &'a TableStore,
+    rng: ChaCha8Rng,
+    refs: HashMap<String, Vec<Value>>,
+    // the keys of the records being replaced, never pointed at
+    replaced: HashMap<String, HashSet<String>>,
+}
+
+impl Generator<'_> {
+    // the fields to make up for `table`, each with where its value comes from
+    fn plan(&self, table: &str) -> Result<BTreeMap<String, Hint>, AppError> {
+        let config = self.store.config(table);
+        let key = config.primary_key();
+        let names = self.store.names();
+        let mut plan: BTreeMap<String, Hint> = BTreeMap::new();
+
+        self.store.read(table, |rows| {
+            for (field, value) in rows.iter().filter_map(Value::as_object).flatten() {
+                if !value.is_null() && !plan.contains_key(field) {
+                    plan.insert(field.clone(), Hint::for_sample(field, value));
+                }
+            }
+        });
+        if let Some(properties) = self
+            .store
+            .schema(table)
+            .and_then(|schema| schema.document().get("properties"))
+            .and_then(Value::as_object)
+        {
+            for (field, schema) in properties {
+                plan.insert(field.clone(), Hint::Schema(schema.clone()));
+            }
+        }
+        // `userId` points at `users` (or `user`) when that table exists
+        for (field, hint) in plan.iter_mut() {
+            let stem = field
+                .strip_suffix("Id")
+                .or_else(|| field.strip_suffix("_id"))
+                .filter(|stem| !stem.is_empty());
+            if let Some(target) = stem.and_then(|stem| {
+                [format!("{}s", stem), stem.to_string()]
+                    .into_iter()
+                    .find(|t| names.contains(t))
+            }) {
+                *hint = Hint::Ref(target);
+            }
+        }
+        for (name, relation) in &config.relations {
+            if relation.kind == RelationKind::BelongsTo {
+                let field = relation
+                    .foreign_key
+                    .clone()
+                    .unwrap_or_else(|| format!("{}Id", name));
+                plan.insert(field, Hint::Ref(relation.table.clone()));
+            }
+        }
+        for (field, spec) in &config.fake {
+            let hint = Hint::parse(spec).ok_or_else(|| {
+                AppError::BadRequest(format!(
+                    "Unknown fake hint {} for {}.{}",
+                    spec, table, field
+                ))
+            })?;
+            plan.insert(field.clone(), hint);
+        }
+
+        plan.remove(key);
+        if plan.is_empty() {
+            return Err(AppError::BadRequest(format!(
+                "Nothing to make up for {}: give it a schema, [tables.{}.fake] hints or a record to copy",
+                table, table
+            )));
+        }
+        Ok(plan)
+    }
+
+    fn record(&mut self, table: &str, plan: &BTreeMap<String, Hint>) -> Value {
+        let mut record = Map::new();
+        let config = self.store.config(table);
+        let id = match config.id_strategy() {
+            IdStrategy::AutoIncrement => None,
+            IdStrategy::UuidV4 | IdStrategy::Client => Some(
+                Builder::from_random_bytes(self.rng.random())
+                    .into_uuid()
+                    .to_string(),
+            ),
+            IdStrategy::UuidV7 => {
+                let millis = self.timestamp() as u64 * 1000;
+                let bytes: [u8; 10] = self.rng.random();
+                Some(
+                    Builder::from_unix_timestamp_millis(millis, &bytes)
+                        .into_uuid()
+                        .to_string(),
+                )
+            }
+            IdStrategy::Ulid => {
+                let millis = self.timestamp() as u64 * 1000;
+                Some(Ulid::from_parts(millis, self.rng.random()).to_string())
+            }
+        };
+        if let Some(id) = id {
+            record.insert(config.primary_key().to_string(), json!(id));
+        }
+        for (field, hint) in plan {
+            let value = self.value(field, hint);
+            record.insert(field.clone(), value);
+        }
+        Value::Object(record)
+    }
+
+    fn timestamp(&mut self) -> i64 {
+        self.rng.random_range(DATE_RANGE.0..DATE_RANGE.1)
+    }
+
+    fn value(&mut self, field: &str, hint: &Hint) -> Value {
+        let rng = &mut self.rng;
+        match hint {
+            Hint::Name => json!(Name().fake_with_rng::<String, _>(rng)),
+            Hint::FirstName => json!(FirstName().fake_with_rng::<String, _>(rng)),
+            Hint::LastName => json!(LastName().fake_with_rng::<String, _>(rng)),
+            Hint::Username => json!(Username().fake_with_rng::<String, _>(rng)),
+            Hint::Email => json!(SafeEmail().fake_with_rng::<String, _>(rng)),
+            Hint::Phone => json!(PhoneNumber().fake_with_rng::<String, _>(rng)),
+            Hint::Company => json!(CompanyName().fake_with_rng::<String, _>(rng)),
+            Hint::Job => json!(JobTitle().fake_with_rng::<String, _>(rng)),
+            Hint::Street => json!(format!(
+                "{} {}",
+                BuildingNumber().fake_with_rng::<String, _>(rng),
+                StreetName().fake_with_rng::<String, _>(rng)
+            )),
+            Hint::City => json!(CityName().fake_with_rng::<String, _>(rng)),
+            Hint::State => json!(StateName().fake_with_rng::<String, _>(rng)),
+            Hint::Zip => json!(ZipCode().fake_with_rng::<String, _>(rng)),
+            Hint::Country => json!(CountryName().fake_with_rng::<String, _>(rng)),
+            Hint::Address => json!(format!(
+                "{} {}, {} {}",
+                BuildingNumber().fake_with_rng::<String, _>(rng),
+                StreetName().fake_with_rng::<String, _>(rng),
+                CityName().fake_with_rng::<String, _>(rng),
+                ZipCode().fake_with_rng::<String, _>(rng)
+            )),
+            Hint::Latitude => json!(round(rng.random_range(-90.0..90.0), 6)),
+            Hint::Longitude => json!(round(rng.random_range(-180.0..180.0), 6)),
+            Hint::Word => json!(Word().fake_with_rng::<String, _>(rng)),
+            Hint::Words => json!(Words(2..5).fake_with_rng::<Vec<String>, _>(rng).join(" ")),
+            Hint::Sentence => json!(Sentence(3..8).fake_with_rng::<String, _>(rng)),
+            Hint::Paragraph => json!(Paragraph(2..5).fake_with_rng::<String, _>(rng)),
+            Hint::LongText => json!(
+                Paragraphs(4..9)
+                    .fake_with_rng::<Vec<String>, _>(rng)
+                    .join("\n\n")
+            ),
+            Hint::Date => json!(date(self.timestamp()).format("%Y-%m-%d").to_string()),
+            Hint::DateTime => json!(
+                date(self.timestamp())
+                    .format("%Y-%m-%dT%H:%M:%SZ")
+                    .to_string()
+            ),
+            Hint::Image => json!(format!(
+                "https://picsum.photos/seed/{}/640/480",
+                rng.random::<u32>()
+            )),
+            Hint::Url => json!(format!(
+                "https://www.{}.com",
+                Word().fake_with_rng::<String, _>(rng)
+            )),
+            Hint::Uuid => json!(
+                Builder::from_random_bytes(rng.random())
+                    .into_uuid()
+                    .to_string()
+            ),
+            Hint::Bool => json!(rng.random_bool(0.5)),
+            Hint::Int(from, to) => json!(rng.random_range(*from.min(to)..=*from.max(to))),
+            Hint::Float(from, to) => {
+                json!(round(rng.random_range(from.min(*to)..=from.max(*to)), 2))
+            }
+            Hint::Price => json!(round(rng.random_range(1.0..500.0), 2)),
+            Hint::Enum(values) if values.is_empty() => Value::Null,
+            Hint::Enum(values) => json!(values[rng.random_range(0..values.len())]),
+            Hint::Ref(table) => {
+                let ids = self.refs.entry(table.clone()).or_insert_with(|| {
+                    let config = self.store.config(table);
+                    let key = config.primary_key();
+                    let replaced = self.replaced.get(table);
+                    self.store.read(table, |rows| {
+                        rows.iter()
+                            .filter(|row| !is_deleted(config, row))
+                            .filter_map(|row| row.get(key))
+                            .filter(|id| {
+                                !replaced.is_some_and(|replaced| {
+                                    id_string(Some(id)).is_some_and(|id| replaced.contains(&id))
+                                })
+                            })
+                            .cloned()
+                            .collect()
+                    })
+                });
+                match ids.len() {
+                    0 => Value::Null,
+                    n => ids[self.rng.random_range(0..n)].clone(),
+                }
+            }
+            Hint::Sample(value) => value.clone(),
+            Hint::Schema(schema) => self.schema_value(field, schema),
+            Hint::Optional(inner) => match rng.random_bool(NULL_RATE) {
+                true => Value::Null,
+                false => self.value(field, inner),
+            },
+        }
+    }
+
+    // a value that passes `schema`, shaped by the field name where it allows
+    fn schema_value(&mut self, field: &str, schema: &Value) -> Value {
+        if let Some(values) = schema.get("enum").and_then(Value::as_array)
+            && !values.is_empty()
+        {
+            return values[self.rng.random_range(0..values.len())].clone();
+        }
+        if let Some(value) = schema.get("const") {
+            return value.clone();
+        }
+        let kind = match schema.get("type") {
+            Some(Value::String(kind)) => kind.as_str(),
+            Some(Value::Array(kinds)) => kinds
+                .iter()
+                .filter_map(Value::as_str)
+                .find(|kind| *kind != "null")
+                .unwrap_or("null"),
+            _ if schema.get("properties").is_some() => "object",
+            _ => "",
+        };
+        let guess = Hint::for_field(field);
+        let bound = |name: &str| schema.get(name).and_then(Value::as_f64);
+
+        match kind {
+            "object" => {
+                let mut obj = Map::new();
+                if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+                    for (name, property) in properties {
+                        let value = self.schema_value(name, property);
+                        obj.insert(name.clone(), value);
+                    }
+                }
+                Value::Object(obj)
+            }
+            "array" => {
+                let items = schema.get("items").cloned().unwrap_or(json!({}));
+                let min = (bound("minItems").unwrap_or(1.0) as usize).min(MAX_ITEMS);
+                let max = bound("maxItems")
+                    .map_or(min + 3, |max| (max as usize).min(MAX_ITEMS))
+                    .max(min);
+                let count = self.rng.random_range(min..=max);
+                Value::Array(
+                    (0..count)
+                        .map(|_| self.schema_value(field, &items))
+                        .collect(),
+                )
+            }
+            "integer" | "number" => {
+                let (from, to) = match guess {
+                    Some(Hint::Int(from, to)) => (from as f64, to as f64),
+                    Some(Hint::Float(from, to)) => (from, to),
+                    Some(Hint::Price) => (1.0, 500.0),
+                    _ => (0.0, 1000.0),
+                };
+                let min = bound("minimum").unwrap_or(from);
+                let max = bound("maximum").unwrap_or(if to < min { min + 1000.0 } else { to });
+                let hint = match kind {
+                    "integer" => Hint::Int(min.ceil() as i64, max.floor() as i64),
+                    _ => Hint::Float(min, max),
+                };
+                self.value(field, &hint)
+            }
+            "boolean" => json!(self.rng.random_bool(0.5)),
+            "null" => Value::Null,
+            _ => {
+                let hint = match schema.get("format").and_then(Value::as_str) {
+                    Some("email") => Hint::Email,
+                    Some("date") => Hint::Date,
+                    Some("date-time") => Hint::DateTime,
+                    Some("uri") | Some("url") => Hint::Url,
+                    Some("uuid") => Hint::Uuid,
+                    _ => guess.unwrap_or(Hint::Words),
+                };
+                let text = match self.value(field, &hint) {
+                    Value::String(text) => text,
+                    other if kind.is_empty() => return other,
+                    other => other.to_string(),
+                };
+                Value::String(self.fit(text, bound("minLength"), bound("maxLength")))
+            }
+        }
+    }
+
+    // pads `text` with words up to `min` characters and cuts it at `max`
+    fn fit(&mut self, mut text: String, min: Option<f64>, max: Option<f64>) -> String {
+        let min = min.unwrap_or(0.0) as usize;
+        while text.chars().count() < min {
+            text.push(' ');
+            text.push_str(&Word().fake_with_rng::<String, _>(&mut self.rng));
+        }
+        match max {
+            Some(max) => text.chars().take(max as usize).collect(),
+            None => text,
+        }
+    }
+}
+
+fn date(timestamp: i64) -> DateTime<chrono::Utc> {
+    DateTime::from_timestamp(timestamp, 0).unwrap_or_default()
+}
+
+fn round(value: f64, places: i32) -> f64 {
+    let factor = 10f64.powi(places);
+    (value * factor).round() / factor
+}
+
+/// Fills the tables of `input` in order, returning the seed used and the
+/// number of records made per table. With `replace` the records already there
+/// are deleted through their `on_delete` rules once every new one is in, and
+/// nothing changes when a table fails.
+pub fn seed_tables(store: &TableStore, input: &SeedInput) -> Result<Value, AppError> {
+    let counts = input.counts()?;
+    let seed = input.seed.unwrap_or_else(|| rand::rng().random());
+    let mut generator = Generator {
+        store,
+        rng: ChaCha8Rng::seed_from_u64(seed),
+        refs: HashMap::new(),
+        replaced: HashMap::new(),
+    };
+
+    // planned up front, so replaced tables can still be copied from
+    let mut plans = vec![];
+    for (table, count) in counts {
+        let plan = generator.plan(&table)?;
+        plans.push((table, count, plan));
+    }
+    if input.replace {
+        for (table, _, _) in &plans {
+            let config = store.config(table);
+            let key = config.primary_key();
+            let ids: HashSet<String> = store.read(table, |rows| {
+                rows.iter()
+                    .filter(|row| !is_deleted(config, row))
+                    .filter_map(|row| id_string(row.get(key)))
+                    .collect()
+            });
+            generator.replaced.insert(table.clone(), ids);
+        }
+    }
+    // a restricting record outside the listed tables fails before anything is made
+    let delete = plan_deletes(store, generator.replaced.clone())?;
+
+    let mut created: Vec<(String, Vec<Value>)> = vec![];
+    for (table, count, plan) in plans {
+        let records: Vec<Value> = (0..count)
+            .map(|_| generator.record(&table, &plan))
+            .collect();
+        match create_many(store, &table, records) {
+            Ok(records) => {
+                generator.refs.remove(&table);
+                created.push((table, records));
+            }
+            Err(err) => {
+                discard(store, &created);
+                return Err(err);
+            }
+        }
+    }
+    apply_delete(store, &delete);
+
+    let tables: Map<String, Value> = created
+        .into_iter()
+        .map(|(table, records)| (table, json!(records.len())))
+        .collect();
+    Ok(json!({ "seed": seed, "tables": tables }))
+}
+
+// takes back the records made before a table failed
+fn discard(store: &TableStore, created: &[(String, Vec<Value>)]) {
+    for (table, records) in created {
+        let key = store.config(table).primary_key();
+        let ids: HashSet<String> = records
+            .iter()
+            .filter_map(|record| id_string(record.get(key)))
+            .collect();
+        store.write(table, |rows| {
+            rows.retain(|row| !id_string(row.get(key)).is_some_and(|id| ids.contains(&id)))
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    use super::seed_tables;
+    use crate::error::AppError;
+    use crate::helpers::schema::TableSchema;
+    use crate::helpers::store::TableStore;
+    use crate::model::seed::SeedInput;
+    use crate::model::toml_config::TableConfig;
+    use crate::storage::memory::MemoryStorage;
+
+    // `fake` is the hints of users, posts must point at a user
+    fn store(fake: &str) -> TableStore {
+        let tables = HashMap::from([
+            (
+                "users".to_string(),
+                vec![json!({ "id": 1, "email": "old@example.com", "age": 20 })],
+            ),
+            (
+                "posts".to_string(),
+                vec![json!({ "id": 1, "title": "Hi", "userId": 1, "views": 3 })],
+            ),
+        ]);
+        let schema = TableSchema::new(json!({
+            "type": "object",
+            "required": ["email", "age"],
+            "properties": {
+                "name": { "type": "string" },
+                "email": { "type": "string", "format": "email" },
+                "age": { "type": "integer", "minimum": 18, "maximum": 30 },
+                "tags": { "type": "array", "items": { "type": "string" }, "maxItems": 2 }
+            }
+        }))
+        .unwrap();
+        TableStore::new(
+            Box::new(MemoryStorage::new(tables)),
+            HashMap::from([
+                (
+                    "users".to_string(),
+                    TableConfig::from_toml(&format!("[fake]\n{}", fake)),
+                ),
+                (
+                    "posts".to_string(),
+                    TableConfig::from_toml(
+                        "[relations.user]\ntype = \"belongs_to\"\ntable = \"users\"",
+                    ),
+                ),
+            ]),
+        )
+        .with_schemas(HashMap::from([("users".to_string(), schema)]))
+    }
+
+    #[test]
+    fn test_seeded_tables_are_reproducible() {
+        let fake = "role = \"enum:admin,member\"\nbio = \"paragraph?\"";
+        let input: SeedInput = serde_json::from_value(json!({
+            "tables": ["users:20", "posts:50"],
+            "seed": 7,
+            "replace": true
+        }))
+        .unwrap();
+
+        let first = store(fake);
+        assert_eq!(
+            seed_tables(&first, &input).unwrap(),
+            json!({ "seed": 7, "tables": { "users": 20, "posts": 50 } })
+        );
+        let second = store(fake);
+        seed_tables(&second, &input).unwrap();
+        for table in ["users", "posts"] {
+            let rows = first.read(table, |rows| rows.clone());
+            assert_eq!(rows, second.read(table, |rows| rows.clone()));
+        }
+
+        let users = first.read("users", |rows| rows.clone());
+        assert_eq!(users.len(), 20);
+        for user in &users {
+            let age = user["age"].as_i64().unwrap();
+            assert!((18..=30).contains(&age));
+            assert!(user["email"].as_str().unwrap().contains('@'));
+            assert!(["admin", "member"].contains(&user["role"].as_str().unwrap()));
+            assert!(user["tags"].as_array().unwrap().len() <= 2);
+        }
+        // posts copy the fields of the old post and point at the new users
+        first.read("posts", |rows| {
+            assert_eq!(rows.len(), 50);
+            for post in rows {
+                assert!(post["title"].is_string() && post["views"].is_i64());
+                let user = post["userId"].as_i64().unwrap();
+                assert!((2..=21).contains(&user));
+            }
+        });
+
+        let input: SeedInput = serde_json::from_value(json!({ "tables": ["nothing"] })).unwrap();
+        assert!(seed_tables(&first, &input).is_err());
+    }
+
+    #[test]
+    fn test_failed_replace_changes_nothing() {
+        let rows = |store: &TableStore| {
+            ["users", "posts"].map(|table| store.read(table, |rows| rows.clone()))
+        };
+
+        // the old post still points at the old user
+        let seeded = store("");
+        let before = rows(&seeded);
+        let input: SeedInput =
+            serde_json::from_value(json!({ "tables": ["users:3"], "replace": true })).unwrap();
+        assert!(matches!(
+            seed_tables(&seeded, &input),
+            Err(AppError::Conflict(_))
+        ));
+        assert_eq!(rows(&seeded), before);
+
+        // the posts made before users break their schema are taken back
+        let seeded = store("age = \"int:40..50\"");
+        let input: SeedInput = serde_json::from_value(json!({
+            "tables": ["posts:5", "users:3"],
+            "replace": true
+        }))
+        .unwrap();
+        assert!(matches!(
+            seed_tables(&seeded, &input),
+            Err(AppError::Invalid(_))
+        ));
+        assert_eq!(rows(&seeded), before);
+    }
+
+    #[test]
+    fn test_seed_limits() {
+        let seeded = store("");
+        for tables in [
+            json!(["users:999999999999"]),
+            json!(["users:6000", "posts:6000"]),
+        ] {
+            let input: SeedInput = serde_json::from_value(json!({ "tables": tables })).unwrap();
+            assert!(matches!(
+                seed_tables(&seeded, &input),
+                Err(AppError::BadRequest(_))
+            ));
+        }
+
+        for fake in ["score = \"float:nan..1\"", "score = \"float:0..inf\""] {
+            let input: SeedInput =
+                serde_json::from_value(json!({ "tables": ["users:1"] })).unwrap();
+            assert!(matches!(
+                seed_tables(&store(fake), &input),
+                Err(AppError::BadRequest(_))
+            ));
+        }
+    }
+}
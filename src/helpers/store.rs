@@ -0,0 +1,165 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+
+use serde_json::Value;
+
+use crate::model::toml_config::TableConfig;
+use crate::storage::Storage;
+
+use super::schema::TableSchema;
+
+// ! in-memory tables
+// each table is loaded from the storage backend the first time it is used and
+// served from memory afterwards. mutations mark the table dirty and `flush`
+// writes dirty tables back through the backend
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Dirty {
+    Clean,
+    Appended(usize),
+    Rewrite,
+}
+
+struct Table {
+    rows: RwLock<Vec<Value>>,
+    dirty: Mutex<Dirty>,
+}
+
+pub struct TableStore {
+    storage: Box<dyn Storage>,
+    configs: HashMap<String, TableConfig>,
+    default_config: TableConfig,
+    schemas: HashMap<String, TableSchema>,
+    tables: RwLock<HashMap<String, Arc<Table>>>,
+    flush_lock: Mutex<()>,
+}
+
+impl TableStore {
+    pub fn new(storage: Box<dyn Storage>, configs: HashMap<String, TableConfig>) -> Self {
+        Self {
+            storage,
+            configs,
+            default_config: TableConfig::default(),
+            schemas: HashMap::new(),
+            tables: RwLock::default(),
+            flush_lock: Mutex::default(),
+        }
+    }
+
+    pub fn with_schemas(mut self, schemas: HashMap<String, TableSchema>) -> Self {
+        self.schemas = schemas;
+        self
+    }
+
+    pub fn schema(&self, name: &str) -> Option<&TableSchema> {
+        self.schemas.get(name)
+    }
+
+    pub fn config(&self, name: &str) -> &TableConfig {
+        self.configs.get(name).unwrap_or(&self.default_config)
+    }
+
+    // every table with its own `[tables.x]` section
+    pub fn configs(&self) -> &HashMap<String, TableConfig> {
+        &self.configs
+    }
+
+    fn table(&self, name: &str) -> Arc<Table> {
+        if let Some(table) = self
+            .tables
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(name)
+        {
+            return table.clone();
+        }
+
+        let mut tables = self.tables.write().unwrap_or_else(PoisonError::into_inner);
+        tables
+            .entry(name.to_string())
+            .or_insert_with(|| {
+                Arc::new(Table {
+                    rows: RwLock::new(self.storage.load(name)),
+                    dirty: Mutex::new(Dirty::Clean),
+                })
+            })
+            .clone()
+    }
+
+    pub fn read<R>(&self, name: &str, f: impl FnOnce(&Vec<Value>) -> R) -> R {
+        let table = self.table(name);
+        let rows = table.rows.read().unwrap_or_else(PoisonError::into_inner);
+        f(&rows)
+    }
+
+    // the table stays locked for the whole closure, so a read-modify-write
+    // cannot interleave with another request on the same table
+    pub fn write<R>(&self, name: &str, f: impl FnOnce(&mut Vec<Value>) -> R) -> R {
+        let table = self.table(name);
+        let mut rows = table.rows.write().unwrap_or_else(PoisonError::into_inner);
+        let res = f(&mut rows);
+        *table.dirty.lock().unwrap_or_else(PoisonError::into_inner) = Dirty::Rewrite;
+        res
+    }
+
+    // like `write` for pure inserts: `f` sees the current rows and returns the
+    // new ones, which backends that support it can append instead of rewriting
+    pub fn insert<E>(
+        &self,
+        name: &str,
+        f: impl FnOnce(&[Value]) -> Result<Vec<Value>, E>,
+    ) -> Result<Vec<Value>, E> {
+        let table = self.table(name);
+        let mut rows = table.rows.write().unwrap_or_else(PoisonError::into_inner);
+        let new_rows = f(&rows)?;
+        rows.extend(new_rows.iter().cloned());
+
+        let mut dirty = table.dirty.lock().unwrap_or_else(PoisonError::into_inner);
+        *dirty = match *dirty {
+            Dirty::Clean => Dirty::Appended(new_rows.len()),
+            Dirty::Appended(n) => Dirty::Appended(n + new_rows.len()),
+            Dirty::Rewrite => Dirty::Rewrite,
+        };
+        Ok(new_rows)
+    }
+
+    // tables known to the backend plus the ones only created in memory so far
+    pub fn names(&self) -> Vec<String> {
+        let mut names: BTreeSet<String> = self
+            .tables
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .keys()
+            .cloned()
+            .collect();
+        names.extend(self.storage.list());
+        names.into_iter().collect()
+    }
+
+    pub fn flush(&self) {
+        let _guard = self
+            .flush_lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let tables: Vec<(String, Arc<Table>)> = self
+            .tables
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(|(name, table)| (name.clone(), table.clone()))
+            .collect();
+
+        for (name, table) in tables {
+            // writers are held off while the table is saved so the dirty state
+            // always describes exactly what changed since the last save
+            let rows = table.rows.read().unwrap_or_else(PoisonError::into_inner);
+            let mut dirty = table.dirty.lock().unwrap_or_else(PoisonError::into_inner);
+            match *dirty {
+                Dirty::Clean => continue,
+                Dirty::Appended(added) => self.storage.append(&name, &rows, added),
+                Dirty::Rewrite => self.storage.save(&name, &rows),
+            }
+            *dirty = Dirty::Clean;
+        }
+    }
+}
@@ -17,16 +17,20 @@ pub fn create_app_config() {
                 auth: Some(AuthType::Jwt),
                 //auth: None,
                 port: 3000,
+                flush_interval_ms: None,
+                storage: None,
+                envelope: None,
             },
+            tables: Default::default(),
         };
 
         let mut file = File::create(format!("{}.toml", "aginisi_config")).unwrap();
-        file.write(toml::to_string_pretty(&config).unwrap().as_bytes())
+        file.write_all(toml::to_string_pretty(&config).unwrap().as_bytes())
             .unwrap();
     }
 }
 
 pub fn read_app_config() -> AppConfig {
-    let content = fs::read_to_string(&format!("{}.toml", "aginisi_config")).unwrap();
-    return toml::from_str(&content).unwrap();
+    let content = fs::read_to_string(format!("{}.toml", "aginisi_config")).unwrap();
+    toml::from_str(&content).unwrap()
 }
@@ -1,18 +1,22 @@
 use std::sync::Arc;
 
+use helpers::store::TableStore;
 use model::toml_config::Config;
 use socketioxide::SocketIo;
 
 pub mod cmd_args;
 pub mod consts;
 pub mod docs;
+pub mod error;
 pub mod helpers;
 pub mod model;
 pub mod routes;
+pub mod storage;
 pub mod utils;
 
 #[derive(Clone)]
 pub struct AppState {
     pub socket_io: Arc<SocketIo>,
     pub config: Config,
+    pub store: Arc<TableStore>,
 }
@@ -1,22 +1,27 @@
 use std::fs::{self};
 use std::sync::Arc;
+use std::time::Duration;
 
-use aginisi::cmd_args::Args;
+use aginisi::cmd_args::{Args, Command};
 use aginisi::consts::{FOLDER_NAME, UPLOAD_FOLDER_NAME};
+use aginisi::helpers::schema::load_schemas;
+use aginisi::helpers::seed::seed_tables;
+use aginisi::helpers::store::TableStore;
 use aginisi::helpers::toml::{create_app_config, read_app_config};
-use aginisi::model::filter_type::FilterType;
+use aginisi::model::seed::SeedInput;
+use aginisi::routes::admin::admin_router;
 use aginisi::routes::auth::auth_router;
 use aginisi::routes::file::file_router;
 use aginisi::routes::{f_route, root};
+use aginisi::storage::storage_from_config;
 use aginisi::{AppState, docs};
-use axum::Router;
 use axum::routing::{any, get};
+use axum::{Json, Router};
 use clap::Parser;
-use serde_json::{Value as SValue, json};
+use serde_json::Value as SValue;
 use socketioxide::SocketIo;
 use socketioxide::extract::{Data, SocketRef};
-use socketioxide::handler::Value;
-use tracing::{debug, info};
+use tracing::info;
 use tracing_subscriber::FmtSubscriber;
 
 //-------------
@@ -26,16 +31,16 @@ async fn main() {
     tracing::subscriber::set_global_default(FmtSubscriber::default()).unwrap();
 
     let args = Args::parse();
-    if let Ok(exist) = fs::exists(FOLDER_NAME) {
-        if !exist {
-            fs::create_dir(FOLDER_NAME).unwrap();
-        }
+    if let Ok(exist) = fs::exists(FOLDER_NAME)
+        && !exist
+    {
+        fs::create_dir(FOLDER_NAME).unwrap();
     }
 
-    if let Ok(exist) = fs::exists(UPLOAD_FOLDER_NAME) {
-        if !exist {
-            fs::create_dir(UPLOAD_FOLDER_NAME).unwrap();
-        }
+    if let Ok(exist) = fs::exists(UPLOAD_FOLDER_NAME)
+        && !exist
+    {
+        fs::create_dir(UPLOAD_FOLDER_NAME).unwrap();
     }
 
     if !args.path.exists() || !args.path.is_dir() {
@@ -43,26 +48,77 @@ async fn main() {
         std::process::exit(1);
     }
 
-    if args.docs == true {
+    if args.docs {
         docs::docs();
         std::process::exit(1);
     }
 
-    let (layer, io) = SocketIo::new_layer();
-    io.ns("/", on_socket_connect);
-    io.ns("/socket", on_socket_connect);
-
     create_app_config();
+    let app_config = read_app_config();
+    let config = app_config.config;
+    let schemas = load_schemas(&app_config.tables).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+    let store = Arc::new(
+        TableStore::new(storage_from_config(&config), app_config.tables).with_schemas(schemas),
+    );
+
+    if let Some(Command::Seed {
+        tables,
+        seed,
+        replace,
+    }) = args.command
+    {
+        let input = SeedInput {
+            tables,
+            seed,
+            replace,
+        };
+        match seed_tables(&store, &input) {
+            Ok(res) => println!("{}", res),
+            Err(err) => {
+                let (_, Json(body)) = err.into();
+                eprintln!("{}", body);
+                std::process::exit(1);
+            }
+        }
+        store.flush();
+        return;
+    }
+
+    let (layer, io) = SocketIo::new_layer();
+    for path in ["/", "/socket"] {
+        let store = store.clone();
+        io.ns(path, move |socket: SocketRef, data: Data<SValue>| {
+            on_socket_connect(socket, data, &store)
+        });
+    }
 
     let state = AppState {
         socket_io: Arc::new(io.clone()),
-        config: read_app_config().config,
+        config: config.clone(),
+        store: store.clone(),
     };
 
+    let flush_interval = Duration::from_millis(config.flush_interval_ms.unwrap_or(500));
+    let flusher = store.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(flush_interval);
+        loop {
+            interval.tick().await;
+            let flusher = flusher.clone();
+            tokio::task::spawn_blocking(move || flusher.flush())
+                .await
+                .ok();
+        }
+    });
+
     let app = Router::new()
         .route("/", get(root))
         .nest("/auth", auth_router(state.clone()))
         .nest("/file", file_router(state.clone()))
+        .nest("/_admin", admin_router(state.clone()))
         .route("/{*path}", any(f_route))
         .layer(layer)
         .with_state(state);
@@ -77,26 +133,44 @@ async fn main() {
         args.path.display(),
         listener.local_addr().unwrap()
     );
-    axum::serve(listener, app).await.unwrap()
-}
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await
+        .unwrap();
 
-pub fn on_socket_connect(socket: SocketRef, Data(data): Data<SValue>) {
-    info!("Socket.IO connected: {:?} {:?}", socket.ns(), socket.id);
-    socket.emit("ping", &data).ok();
+    info!("Writing tables to disk");
+    store.flush();
+}
 
-    let entries = fs::read_dir(FOLDER_NAME).unwrap();
-    let mut names = Vec::<String>::new();
-    for entry in entries {
-        let entry = entry.unwrap();
-        let path = entry.path();
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.ok();
+    };
 
-        if path.is_file() {
-            if let Some(name) = path.file_name().and_then(|t| t.to_str()) {
-                names.push(name.strip_suffix(".json").unwrap().to_string());
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
             }
+            Err(_) => std::future::pending::<()>().await,
         }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
     }
+}
+
+pub fn on_socket_connect(socket: SocketRef, Data(data): Data<SValue>, store: &TableStore) {
+    info!("Socket.IO connected: {:?} {:?}", socket.ns(), socket.id);
+    socket.emit("ping", &data).ok();
 
+    let names = store.names();
     for name in names {
         socket.on(
             name.clone(),
@@ -0,0 +1,250 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use crate::utils::compare::compare_values;
+use crate::utils::field_path::{resolve, resolve_first};
+
+// ! {"group_by": ["status"], "metrics": [{"op": "sum", "key": "total"}, {"op": "avg", "key": "age", "alias": "avg_age"}]}
+// ! GET /orders/_aggregate?_group_by=status&_sum=total&_avg=age
+// every result carries `count`. without group_by the whole table is one group
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AggregateOp {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+    Distinct,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Metric {
+    pub op: AggregateOp,
+    pub key: Option<String>,   // the field to aggregate, not needed for count
+    pub alias: Option<String>, // "{op}_{key}" by default
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Aggregate {
+    #[serde(default)]
+    pub group_by: Vec<String>,
+    #[serde(default)]
+    pub metrics: Vec<Metric>,
+}
+
+const QUERY_METRICS: &[(&str, AggregateOp)] = &[
+    ("_sum", AggregateOp::Sum),
+    ("_avg", AggregateOp::Avg),
+    ("_min", AggregateOp::Min),
+    ("_max", AggregateOp::Max),
+    ("_distinct", AggregateOp::Distinct),
+];
+
+fn split_list(value: &str) -> impl Iterator<Item = String> + '_ {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .map(str::to_string)
+}
+
+impl Aggregate {
+    // `None` when the query string asks for nothing to aggregate
+    pub fn from_query(params: &HashMap<String, String>) -> Option<Aggregate> {
+        let group_by: Vec<String> = params
+            .get("_group_by")
+            .map(|fields| split_list(fields).collect())
+            .unwrap_or_default();
+        let metrics: Vec<Metric> = QUERY_METRICS
+            .iter()
+            .filter_map(|(param, op)| Some((params.get(*param)?, *op)))
+            .flat_map(|(fields, op)| {
+                split_list(fields).map(move |key| Metric {
+                    op,
+                    key: Some(key),
+                    alias: None,
+                })
+            })
+            .collect();
+
+        if group_by.is_empty() && metrics.is_empty() {
+            return None;
+        }
+        Some(Aggregate { group_by, metrics })
+    }
+
+    /// One result object per group, holding the group fields, `count` and the
+    /// metrics. Groups are ordered by their group fields.
+    pub fn apply(&self, rows: &[&Value]) -> Vec<Value> {
+        let mut groups: BTreeMap<String, (Vec<Value>, Vec<&Value>)> = BTreeMap::new();
+        if self.group_by.is_empty() {
+            // an empty table still has a (zero) summary
+            groups.insert(String::new(), (vec![], vec![]));
+        }
+        for row in rows {
+            let key: Vec<Value> = self
+                .group_by
+                .iter()
+                .map(|field| resolve_first(row, field).cloned().unwrap_or(Value::Null))
+                .collect();
+            let id = match self.group_by.is_empty() {
+                true => String::new(),
+                false => Value::Array(key.clone()).to_string(),
+            };
+            groups
+                .entry(id)
+                .or_insert_with(|| (key, vec![]))
+                .1
+                .push(row);
+        }
+
+        let mut groups: Vec<(Vec<Value>, Vec<&Value>)> = groups.into_values().collect();
+        groups.sort_by(|(a, _), (b, _)| {
+            a.iter()
+                .zip(b)
+                .map(|(x, y)| compare_values(x, y))
+                .find(|ord| ord.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+
+        groups
+            .into_iter()
+            .map(|(key, rows)| {
+                let mut result = Map::new();
+                for (field, value) in self.group_by.iter().zip(key) {
+                    result.insert(field.clone(), value);
+                }
+                result.insert("count".to_string(), json!(rows.len()));
+                for metric in &self.metrics {
+                    let name = match (&metric.alias, &metric.key) {
+                        (Some(alias), _) => alias.clone(),
+                        (None, Some(key)) => format!("{}_{}", op_name(metric.op), key),
+                        (None, None) => op_name(metric.op).to_string(),
+                    };
+                    result.insert(name, metric.compute(&rows));
+                }
+                Value::Object(result)
+            })
+            .collect()
+    }
+}
+
+fn op_name(op: AggregateOp) -> &'static str {
+    match op {
+        AggregateOp::Count => "count",
+        AggregateOp::Sum => "sum",
+        AggregateOp::Avg => "avg",
+        AggregateOp::Min => "min",
+        AggregateOp::Max => "max",
+        AggregateOp::Distinct => "distinct",
+    }
+}
+
+impl Metric {
+    // nulls and missing fields are skipped, sum and avg only look at numbers
+    fn compute(&self, rows: &[&Value]) -> Value {
+        let values: Vec<&Value> = match &self.key {
+            Some(key) => rows
+                .iter()
+                .flat_map(|row| resolve(row, key))
+                .filter(|v| !v.is_null())
+                .collect(),
+            None => rows.to_vec(),
+        };
+        let numbers = || values.iter().filter_map(|v| v.as_f64());
+
+        match self.op {
+            AggregateOp::Count => json!(values.len()),
+            AggregateOp::Sum => number(numbers().sum()),
+            AggregateOp::Avg => {
+                let count = numbers().count();
+                match count {
+                    0 => Value::Null,
+                    _ => number(numbers().sum::<f64>() / count as f64),
+                }
+            }
+            AggregateOp::Min => values
+                .iter()
+                .min_by(|a, b| compare_values(a, b))
+                .map_or(Value::Null, |v| (*v).clone()),
+            AggregateOp::Max => values
+                .iter()
+                .max_by(|a, b| compare_values(a, b))
+                .map_or(Value::Null, |v| (*v).clone()),
+            AggregateOp::Distinct => {
+                let mut distinct: Vec<Value> = vec![];
+                for value in values {
+                    if !distinct.contains(value) {
+                        distinct.push(value.clone());
+                    }
+                }
+                distinct.sort_by(compare_values);
+                Value::Array(distinct)
+            }
+        }
+    }
+}
+
+// whole results stay integers
+fn number(value: f64) -> Value {
+    if value.fract() == 0.0 && value.abs() < i64::MAX as f64 {
+        json!(value as i64)
+    } else {
+        json!(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::{Value, json};
+
+    use super::Aggregate;
+
+    #[test]
+    fn test_group_by_and_metrics() {
+        let rows = [
+            json!({ "status": "paid", "total": 10, "age": 30, "city": "Lagos" }),
+            json!({ "status": "paid", "total": 15.5, "age": 20, "city": "Abuja" }),
+            json!({ "status": "open", "total": 7, "city": "Lagos" }),
+            json!({ "total": 1, "age": null }),
+        ];
+        let rows: Vec<&Value> = rows.iter().collect();
+
+        let params = HashMap::from([
+            ("_group_by".to_string(), "status".to_string()),
+            ("_sum".to_string(), "total".to_string()),
+            ("_avg".to_string(), "age".to_string()),
+            ("_distinct".to_string(), "city".to_string()),
+        ]);
+        let aggregate = Aggregate::from_query(&params).unwrap();
+        assert_eq!(
+            aggregate.apply(&rows),
+            [
+                json!({ "status": null, "count": 1, "sum_total": 1, "avg_age": null, "distinct_city": [] }),
+                json!({ "status": "open", "count": 1, "sum_total": 7, "avg_age": null, "distinct_city": ["Lagos"] }),
+                json!({ "status": "paid", "count": 2, "sum_total": 25.5, "avg_age": 25, "distinct_city": ["Abuja", "Lagos"] }),
+            ]
+        );
+
+        let aggregate: Aggregate = serde_json::from_value(json!({
+            "metrics": [
+                { "op": "min", "key": "total" },
+                { "op": "max", "key": "total", "alias": "largest" },
+                { "op": "count", "key": "age" }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(
+            aggregate.apply(&rows),
+            [json!({ "count": 4, "min_total": 1, "largest": 15.5, "count_age": 2 })]
+        );
+        assert!(Aggregate::from_query(&HashMap::new()).is_none());
+    }
+}
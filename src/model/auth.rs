@@ -1,5 +1,6 @@
 use jsonwebtoken::{DecodingKey, EncodingKey};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SignUpInput {
@@ -27,7 +28,20 @@ pub struct Claims {
     pub iat: usize,  // Issued at (as UTC timestamp) // ! optional
     pub iss: String, // Issuer // ! optional
     pub nbf: usize,  // Not Before (as UTC timestamp) // ! optional
-    pub sub: i64,    // Subject (whom the token refers to) // ! optional
+    #[serde(deserialize_with = "subject")]
+    pub sub: String, // Subject (whom the token refers to) // ! optional
+}
+
+// tokens issued before keys could be strings carry a numeric subject
+fn subject<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::String(sub) => Ok(sub),
+        Value::Number(sub) => Ok(sub.to_string()),
+        other => Err(serde::de::Error::custom(format!(
+            "invalid subject {}",
+            other
+        ))),
+    }
 }
 
 pub struct Keys {
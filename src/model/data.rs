@@ -1,11 +1,16 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use super::{filter_type::FilterType, sort_type::SortType};
+use super::{
+    aggregate::Aggregate, filter_type::FilterType, projection::Projection, sort_type::SortBy,
+};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Data {
     pub filter: Option<FilterType>,
-    pub sort: Option<SortType>,
+    pub r#where: Option<String>, // ! "age >= 21 and not deleted"
+    pub sort: Option<SortBy>,
+    pub select: Option<Projection>, // ! "id,name:full_name,-password" or a list of fields
     pub data: Option<Value>,
+    pub aggregate: Option<Aggregate>,
 }
@@ -1,8 +1,23 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::consts::RESERVED_PARAMS;
+use crate::utils::compare::{compare_operand, values_equal};
+use crate::utils::field_path::resolve;
+
 // ! {"type": "Equals", "key": "name", "value": "run"}
+// ! {"type": "Equals", "key": "address.city", "value": "Lagos"}
+// ! {"type": "Like", "key": "items[*].sku", "pattern": "AB-%"}
+// ! {"type": "Between", "key": "age", "from": 18, "to": 30}
+// ! {"type": "Not", "inner": {"type": "Exists", "key": "email"}}
+// ! {"type": "GreaterThanOrEqualsTo", "key": "created_at", "value": "now-7d"}
+// ! {"type": "ArrayContainsAll", "key": "roles", "value": ["admin", "editor"]}
+// ! {"type": "ElemMatch", "key": "items", "filter": {"type": "GreaterThan", "key": "qty", "value": 2}}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
@@ -31,8 +46,16 @@ pub enum FilterType {
         key: Value,
         value: Value,
     }, //column, value
-    // Between,
-    // NoBetween,
+    Between {
+        key: Value,
+        from: Value,
+        to: Value,
+    }, //column, inclusive range
+    NotBetween {
+        key: Value,
+        from: Value,
+        to: Value,
+    }, //column, inclusive range
     InSet {
         key: Value,
         value: Value,
@@ -43,12 +66,58 @@ pub enum FilterType {
     }, //column, value (string, List<string>)
     Like {
         key: Value,
-        pattern: Value,
+        pattern: Pattern,
     },
     NotLike {
         key: Value,
-        pattern: Value,
+        pattern: Pattern,
+    },
+    ILike {
+        key: Value,
+        pattern: Pattern,
+    }, //column, like pattern matched ignoring case
+    StartsWith {
+        key: Value,
+        value: Value,
+    },
+    EndsWith {
+        key: Value,
+        value: Value,
     },
+    Regex {
+        key: Value,
+        pattern: Pattern,
+    }, //column, regular expression
+    IsNull {
+        key: Value,
+    }, //column is null or missing
+    Exists {
+        key: Value,
+    }, //column is present, even when null
+
+    // ? arrays
+    ArrayContains {
+        key: Value,
+        value: Value,
+    }, //column (list), element
+    ArrayContainsAny {
+        key: Value,
+        value: Value,
+    }, //column (list), List of elements
+    ArrayContainsAll {
+        key: Value,
+        value: Value,
+    }, //column (list), List of elements
+    ArrayLength {
+        key: Value,
+        #[serde(default)]
+        op: CompareOp,
+        value: Value,
+    }, //column (list), length
+    ElemMatch {
+        key: Value,
+        filter: Box<FilterType>,
+    }, //column (list), filter on each element. key "" is the element itself
 
     // ? comparison
     And {
@@ -62,115 +131,450 @@ pub enum FilterType {
     Not {
         inner: Box<FilterType>,
     },
+    All {
+        filters: Vec<FilterType>,
+    }, //every filter matches
+    Any {
+        filters: Vec<FilterType>,
+    }, //at least one filter matches
+}
+
+// a Like, ILike or Regex pattern. it is compiled the first time it is needed
+// and kept with the filter, so a request compiles each pattern once
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "Value", into = "Value")]
+pub struct Pattern {
+    source: Value,
+    compiled: OnceLock<Result<regex::Regex, String>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Syntax {
+    Like,
+    ILike,
+    Regex,
+}
+
+impl From<Value> for Pattern {
+    fn from(source: Value) -> Self {
+        Pattern {
+            source,
+            compiled: OnceLock::new(),
+        }
+    }
+}
+
+impl From<Pattern> for Value {
+    fn from(pattern: Pattern) -> Self {
+        pattern.source
+    }
+}
+
+impl Pattern {
+    // a pattern is only ever read with the syntax of its filter
+    fn regex(&self, syntax: Syntax) -> Result<&regex::Regex, &str> {
+        self.compiled
+            .get_or_init(|| {
+                let Some(source) = self.source.as_str() else {
+                    return Err(format!("Pattern {} is not a string", self.source));
+                };
+                let regex = match syntax {
+                    Syntax::Regex => regex::Regex::new(source),
+                    Syntax::Like | Syntax::ILike => {
+                        like_pattern_to_regex(source, syntax == Syntax::ILike)
+                    }
+                };
+                regex.map_err(|e| format!("Invalid pattern {}: {}", self.source, e))
+            })
+            .as_ref()
+            .map_err(String::as_str)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompareOp {
+    #[default]
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+}
+
+impl CompareOp {
+    pub fn matches(self, ord: Ordering) -> bool {
+        match self {
+            CompareOp::Eq => ord.is_eq(),
+            CompareOp::Ne => ord.is_ne(),
+            CompareOp::Gt => ord.is_gt(),
+            CompareOp::Gte => ord.is_ge(),
+            CompareOp::Lt => ord.is_lt(),
+            CompareOp::Lte => ord.is_le(),
+        }
+    }
 }
 
 impl FilterType {
     pub fn evaluate(&self, row: &Value) -> bool {
         use FilterType::*;
 
-        fn get_field_value<'a>(row: &'a Value, key: &Value) -> Option<&'a Value> {
-            key.as_str().and_then(|k| row.get(k))
+        // a key is a field path, and a path with a wildcard matches the row
+        // when any of the values it resolves to does. a missing field matches
+        // nothing, only IsNull and Exists look at absent fields
+        fn any_field(row: &Value, key: &Value, test: impl Fn(&Value) -> bool) -> bool {
+            key.as_str()
+                .is_some_and(|k| resolve(row, k).into_iter().any(test))
+        }
+
+        fn any_str(row: &Value, key: &Value, test: impl Fn(&str) -> bool) -> bool {
+            any_field(row, key, |v| v.as_str().is_some_and(&test))
+        }
+
+        fn any_array(row: &Value, key: &Value, test: impl Fn(&Vec<Value>) -> bool) -> bool {
+            any_field(row, key, |v| v.as_array().is_some_and(&test))
+        }
+
+        fn contains(arr: &[Value], value: &Value) -> bool {
+            arr.iter().any(|x| values_equal(x, value))
         }
 
         // k => field/ key
         // v => value
 
         match self {
-            Equals { key, value } => get_field_value(row, key).map_or(false, |v| v == value),
-            NotEquals { key, value } => get_field_value(row, key).map_or(false, |v| v != value),
-            GreaterThan { key, value } => {
-                get_field_value(row, key).and_then(|v| v.as_f64()) > value.as_f64()
-            }
-            GreaterThanOrEqualsTo { key, value } => {
-                get_field_value(row, key).and_then(|v| v.as_f64()) >= value.as_f64()
-            }
-            LessThanThan { key, value } => {
-                get_field_value(row, key).and_then(|v| v.as_f64()) < value.as_f64()
-            }
-            LessThanThanOrEqualsTo { key, value } => {
-                get_field_value(row, key).and_then(|v| v.as_f64()) <= value.as_f64()
-            }
+            Equals { key, value } => any_field(row, key, |v| values_equal(v, value)),
+            NotEquals { key, value } => any_field(row, key, |v| !values_equal(v, value)),
+            GreaterThan { key, value } => any_field(row, key, |v| {
+                compare_operand(v, value).is_some_and(Ordering::is_gt)
+            }),
+            GreaterThanOrEqualsTo { key, value } => any_field(row, key, |v| {
+                compare_operand(v, value).is_some_and(Ordering::is_ge)
+            }),
+            LessThanThan { key, value } => any_field(row, key, |v| {
+                compare_operand(v, value).is_some_and(Ordering::is_lt)
+            }),
+            LessThanThanOrEqualsTo { key, value } => any_field(row, key, |v| {
+                compare_operand(v, value).is_some_and(Ordering::is_le)
+            }),
             InSet { key, value } => {
-                if value.is_array() {
-                    let val = value.as_array().unwrap();
-                    let res = get_field_value(row, key).unwrap();
-                    return val.contains(res);
-                }
-                false
+                let Some(set) = value.as_array() else {
+                    return false;
+                };
+                any_field(row, key, |v| set.iter().any(|x| values_equal(v, x)))
             }
             NotInSet { key, value } => {
-                if value.is_array() {
-                    let val = value.as_array().unwrap();
-                    let res = get_field_value(row, key).unwrap();
-                    return !val.contains(res);
-                }
-                false
+                let Some(set) = value.as_array() else {
+                    return false;
+                };
+                any_field(row, key, |v| !set.iter().any(|x| values_equal(v, x)))
             }
+            Between { key, from, to } => any_field(row, key, |v| {
+                compare_operand(v, from).is_some_and(Ordering::is_ge)
+                    && compare_operand(v, to).is_some_and(Ordering::is_le)
+            }),
+            NotBetween { key, from, to } => any_field(row, key, |v| {
+                compare_operand(v, from).is_some_and(Ordering::is_lt)
+                    || compare_operand(v, to).is_some_and(Ordering::is_gt)
+            }),
             Like { key, pattern } => {
-                let Some(field) = get_field_value(row, key) else {
+                let Ok(regex) = pattern.regex(Syntax::Like) else {
                     return false;
                 };
-                let Some(field_str) = field.as_str() else {
+                any_str(row, key, |s| regex.is_match(s))
+            }
+            NotLike { key, pattern } => {
+                let Ok(regex) = pattern.regex(Syntax::Like) else {
                     return false;
                 };
-                let Some(pattern_str) = pattern.as_str() else {
+                any_str(row, key, |s| !regex.is_match(s))
+            }
+            ILike { key, pattern } => {
+                let Ok(regex) = pattern.regex(Syntax::ILike) else {
                     return false;
                 };
-                let Some(regex) = like_pattern_to_regex(pattern_str) else {
+                any_str(row, key, |s| regex.is_match(s))
+            }
+            StartsWith { key, value } => {
+                let Some(prefix) = value.as_str() else {
                     return false;
                 };
-
-                regex.is_match(field_str)
+                any_str(row, key, |s| s.starts_with(prefix))
             }
-            NotLike { key, pattern } => {
-                let Some(field) = get_field_value(row, key) else {
+            EndsWith { key, value } => {
+                let Some(suffix) = value.as_str() else {
                     return false;
                 };
-                let Some(field_str) = field.as_str() else {
+                any_str(row, key, |s| s.ends_with(suffix))
+            }
+            Regex { key, pattern } => {
+                let Ok(regex) = pattern.regex(Syntax::Regex) else {
                     return false;
                 };
-                let Some(pattern_str) = pattern.as_str() else {
+                any_str(row, key, |s| regex.is_match(s))
+            }
+            IsNull { key } => {
+                let fields = key.as_str().map(|k| resolve(row, k)).unwrap_or_default();
+                fields.is_empty() || fields.iter().any(|v| v.is_null())
+            }
+            Exists { key } => key.as_str().is_some_and(|k| !resolve(row, k).is_empty()),
+            ArrayContains { key, value } => any_array(row, key, |arr| contains(arr, value)),
+            ArrayContainsAny { key, value } => {
+                let Some(set) = value.as_array() else {
                     return false;
                 };
-                let Some(regex) = like_pattern_to_regex(pattern_str) else {
+                any_array(row, key, |arr| set.iter().any(|x| contains(arr, x)))
+            }
+            ArrayContainsAll { key, value } => {
+                let Some(set) = value.as_array() else {
                     return false;
                 };
-
-                !regex.is_match(field_str)
+                any_array(row, key, |arr| set.iter().all(|x| contains(arr, x)))
+            }
+            ArrayLength { key, op, value } => {
+                let Some(len) = value.as_u64() else {
+                    return false;
+                };
+                any_array(row, key, |arr| op.matches((arr.len() as u64).cmp(&len)))
+            }
+            ElemMatch { key, filter } => {
+                any_array(row, key, |arr| arr.iter().any(|elem| filter.evaluate(elem)))
             }
             And { left, right } => left.evaluate(row) && right.evaluate(row),
             Or { left, right } => left.evaluate(row) || right.evaluate(row),
             Not { inner } => !inner.evaluate(row),
+            All { filters } => filters.iter().all(|filter| filter.evaluate(row)),
+            Any { filters } => filters.iter().any(|filter| filter.evaluate(row)),
+        }
+    }
+}
+
+impl FilterType {
+    /// Compiles every pattern of the filter up front, failing with the first
+    /// one that does not compile.
+    pub fn validate(&self) -> Result<(), String> {
+        use FilterType::*;
+
+        let compile = |pattern: &Pattern, syntax| {
+            pattern.regex(syntax).map(|_| ()).map_err(|e| e.to_string())
+        };
+        match self {
+            Like { pattern, .. } | NotLike { pattern, .. } => compile(pattern, Syntax::Like),
+            ILike { pattern, .. } => compile(pattern, Syntax::ILike),
+            Regex { pattern, .. } => compile(pattern, Syntax::Regex),
+            ElemMatch { filter, .. } => filter.validate(),
+            And { left, right } | Or { left, right } => {
+                left.validate()?;
+                right.validate()
+            }
+            Not { inner } => inner.validate(),
+            All { filters } | Any { filters } => filters.iter().try_for_each(FilterType::validate),
+            _ => Ok(()),
+        }
+    }
+}
+
+// ! ?age_gte=21&name_like=A%25&status_in=a,b
+// every parameter that is not an option becomes a filter on its field, and the
+// filters are combined with And. a suffix picks the operator, Equals otherwise.
+// a value that reads as a number, boolean or null also matches string fields
+// holding the same text, so `?zip=123` finds `"zip": "123"`
+impl FilterType {
+    pub fn from_query(params: &HashMap<String, String>) -> Option<FilterType> {
+        let mut keys: Vec<&String> = params
+            .keys()
+            .filter(|k| !RESERVED_PARAMS.contains(&k.as_str()))
+            .collect();
+        keys.sort();
+
+        let filters = keys
+            .into_iter()
+            .map(|param| {
+                let raw = params[param].as_str();
+                let (field, op) = match param.rsplit_once('_') {
+                    Some((field, op)) if !field.is_empty() && QUERY_OPERATORS.contains(&op) => {
+                        (field, op)
+                    }
+                    _ => (param.as_str(), "eq"),
+                };
+                let filter = query_filter(field, op, raw, query_value);
+                let typed = raw
+                    .split(',')
+                    .chain([raw])
+                    .any(|part| !query_value(part).is_string());
+                if !typed || !VALUE_OPERATORS.contains(&op) {
+                    return filter;
+                }
+                let text = query_filter(field, op, raw, |raw| Value::String(raw.to_string()));
+                match op {
+                    "ne" | "nin" => FilterType::All {
+                        filters: vec![filter, text],
+                    },
+                    _ => FilterType::Any {
+                        filters: vec![filter, text],
+                    },
+                }
+            })
+            .collect();
+        FilterType::all(filters)
+    }
+
+    /// Combines filters so every one of them must match. `None` when there are none.
+    pub fn all(mut filters: Vec<FilterType>) -> Option<FilterType> {
+        match filters.len() {
+            0 => None,
+            1 => filters.pop(),
+            _ => Some(FilterType::All { filters }),
         }
     }
 }
 
-fn like_pattern_to_regex(like: &str) -> Option<Regex> {
-    let mut regex_string = String::from("^");
-    let mut chars = like.chars();
+const QUERY_OPERATORS: &[&str] = &[
+    "ne",
+    "gt",
+    "gte",
+    "lt",
+    "lte",
+    "in",
+    "nin",
+    "like",
+    "nlike",
+    "ilike",
+    "regex",
+    "startswith",
+    "endswith",
+    "between",
+    "null",
+    "exists",
+    "contains",
+    "containsany",
+    "containsall",
+    "length",
+];
+
+// the operators whose value is compared with the field
+const VALUE_OPERATORS: &[&str] = &[
+    "eq",
+    "ne",
+    "gt",
+    "gte",
+    "lt",
+    "lte",
+    "in",
+    "nin",
+    "between",
+    "contains",
+    "containsany",
+    "containsall",
+];
+
+// the filter of one parameter, with `value_of` reading its values
+fn query_filter(field: &str, op: &str, raw: &str, value_of: fn(&str) -> Value) -> FilterType {
+    use FilterType::*;
+
+    let key = Value::String(field.to_string());
+    let value = value_of(raw);
+    let list = || Value::Array(raw.split(',').map(value_of).collect());
+    let text = || Value::String(raw.to_string());
+    let pattern = || Pattern::from(text());
+
+    match op {
+        "ne" => NotEquals { key, value },
+        "gt" => GreaterThan { key, value },
+        "gte" => GreaterThanOrEqualsTo { key, value },
+        "lt" => LessThanThan { key, value },
+        "lte" => LessThanThanOrEqualsTo { key, value },
+        "in" => InSet { key, value: list() },
+        "nin" => NotInSet { key, value: list() },
+        "like" => Like {
+            key,
+            pattern: pattern(),
+        },
+        "nlike" => NotLike {
+            key,
+            pattern: pattern(),
+        },
+        "ilike" => ILike {
+            key,
+            pattern: pattern(),
+        },
+        "regex" => Regex {
+            key,
+            pattern: pattern(),
+        },
+        "startswith" => StartsWith { key, value: text() },
+        "endswith" => EndsWith { key, value: text() },
+        "between" => match raw.split_once(',') {
+            Some((from, to)) => Between {
+                key,
+                from: value_of(from),
+                to: value_of(to),
+            },
+            None => Equals { key, value },
+        },
+        // `?email_null=true` or `?email_null=false`
+        "null" if value == Value::Bool(false) => Not {
+            inner: Box::new(IsNull { key }),
+        },
+        "null" => IsNull { key },
+        "exists" if value == Value::Bool(false) => Not {
+            inner: Box::new(Exists { key }),
+        },
+        "exists" => Exists { key },
+        "contains" => ArrayContains { key, value },
+        "containsany" => ArrayContainsAny { key, value: list() },
+        "containsall" => ArrayContainsAll { key, value: list() },
+        "length" => ArrayLength {
+            key,
+            op: CompareOp::Eq,
+            value,
+        },
+        _ => Equals { key, value },
+    }
+}
 
-    while let Some(c) = chars.next() {
+// numbers, booleans and null keep their json type, anything else is a string
+fn query_value(raw: &str) -> Value {
+    match serde_json::from_str::<Value>(raw) {
+        Ok(value @ (Value::Number(_) | Value::Bool(_) | Value::Null)) => value,
+        _ => Value::String(raw.to_string()),
+    }
+}
+
+// `%` matches any run of characters and `_` a single one, everything else is literal
+fn like_pattern_to_regex(like: &str, ignore_case: bool) -> Result<regex::Regex, regex::Error> {
+    let mut regex_string = String::from(if ignore_case { "(?is)^" } else { "(?s)^" });
+    for c in like.chars() {
         match c {
             '%' => regex_string.push_str(".*"),
             '_' => regex_string.push('.'),
-            '.' | '+' | '(' | ')' | '|' | '^' | '$' | '[' | ']' | '{' | '}' | '\\' => {
-                regex_string.push('\\');
-                regex_string.push(c);
-            }
-            other => regex_string.push(other),
+            other => regex_string.push_str(&regex::escape(other.encode_utf8(&mut [0; 4]))),
         }
     }
 
     regex_string.push('$');
-    Regex::new(&regex_string).ok()
+    Regex::new(&regex_string)
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
+    use chrono::{TimeDelta, Utc};
     use serde_json::{Value, json};
 
     use super::FilterType;
 
+    // the ids of the `data` rows the json `filter` keeps
+    fn matching(data: &[Value], filter: Value) -> Vec<i64> {
+        let filter: FilterType = serde_json::from_value(filter).unwrap();
+        data.iter()
+            .filter(|row| filter.evaluate(row))
+            .map(|row| row["id"].as_i64().unwrap())
+            .collect()
+    }
+
     // const DATA: Vec<Value> = vec![
     //     json!({ "name": "Alice", "age": 31 }),
     //     json!({ "name": "Bob", "age": 25 }),
@@ -221,4 +625,255 @@ mod tests {
 
         assert_eq!(filtered.len(), 2);
     }
+
+    #[test]
+    fn test_from_query() {
+        let data = vec![
+            json!({ "name": "Alice", "age": 31, "status": "a" }),
+            json!({ "name": "Bob", "age": 25, "status": "b" }),
+            json!({ "name": "Alice", "age": 19, "status": "c" }),
+            json!({ "name": "Anna", "age": 40, "status": "b" }),
+        ];
+        let params = HashMap::from([
+            ("age_gte".to_string(), "21".to_string()),
+            ("name_like".to_string(), "A%".to_string()),
+            ("status_in".to_string(), "a,b".to_string()),
+            ("limit".to_string(), "5".to_string()),
+        ]);
+        let filter = FilterType::from_query(&params).unwrap();
+        let filtered: Vec<_> = data
+            .into_iter()
+            .filter(|row| filter.evaluate(row))
+            .collect();
+
+        assert_eq!(filtered.len(), 2);
+        assert!(FilterType::from_query(&HashMap::new()).is_none());
+    }
+
+    #[test]
+    fn test_query_numbers_match_text() {
+        let data = [
+            json!({ "id": 1, "zip": "123", "code": "007" }),
+            json!({ "id": 2, "zip": 123, "code": 7 }),
+            json!({ "id": 3, "zip": "456", "code": "true" }),
+        ];
+        let ids = |params: &[(&str, &str)]| -> Vec<i64> {
+            let params = params
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            let filter = FilterType::from_query(&params).unwrap();
+            data.iter()
+                .filter(|row| filter.evaluate(row))
+                .map(|row| row["id"].as_i64().unwrap())
+                .collect()
+        };
+
+        assert_eq!(ids(&[("zip", "123")]), [1, 2]);
+        assert_eq!(ids(&[("zip_ne", "123")]), [3]);
+        assert_eq!(ids(&[("zip_in", "456,123")]), [1, 2, 3]);
+        assert_eq!(ids(&[("zip_nin", "123")]), [3]);
+        assert_eq!(ids(&[("code", "007")]), [1]);
+        assert_eq!(ids(&[("code", "true")]), [3]);
+    }
+
+    #[test]
+    fn test_nested_paths() {
+        let data = [
+            json!({
+                "id": 1,
+                "address": { "city": "Lagos" },
+                "tags": ["a", "b"],
+                "items": [{ "sku": "AB-1" }, { "sku": "CD-2" }]
+            }),
+            json!({
+                "id": 2,
+                "address": { "city": "Accra" },
+                "tags": ["b"],
+                "items": [{ "sku": "CD-3" }]
+            }),
+            json!({ "id": 3, "address.city": "Lagos", "items": [] }),
+        ];
+        let count = |filter: Value| {
+            let filter: FilterType = serde_json::from_value(filter).unwrap();
+            data.iter().filter(|row| filter.evaluate(row)).count()
+        };
+
+        assert_eq!(
+            count(json!({ "type": "Equals", "key": "address.city", "value": "Lagos" })),
+            2
+        );
+        assert_eq!(
+            count(json!({ "type": "Equals", "key": "/tags/0", "value": "b" })),
+            1
+        );
+        assert_eq!(
+            count(json!({ "type": "Equals", "key": "tags[1]", "value": "b" })),
+            1
+        );
+        assert_eq!(
+            count(json!({ "type": "Like", "key": "items[*].sku", "pattern": "CD-%" })),
+            2
+        );
+        assert_eq!(
+            count(json!({ "type": "InSet", "key": "missing.field", "value": ["x"] })),
+            0
+        );
+    }
+
+    #[test]
+    fn test_string_and_null_operators() {
+        let data = [
+            json!({ "id": 1, "name": "Alice", "age": 31, "email": "alice@mail.com" }),
+            json!({ "id": 2, "name": "bob", "age": 25, "email": null }),
+            json!({ "id": 3, "name": "Alina", "age": 19 }),
+        ];
+        let ids = |filter: Value| matching(&data, filter);
+
+        assert_eq!(
+            ids(json!({ "type": "Between", "key": "age", "from": 19, "to": 25 })),
+            [2, 3]
+        );
+        assert_eq!(
+            ids(json!({ "type": "NotBetween", "key": "age", "from": 19, "to": 25 })),
+            [1]
+        );
+        assert_eq!(ids(json!({ "type": "IsNull", "key": "email" })), [2, 3]);
+        assert_eq!(ids(json!({ "type": "Exists", "key": "email" })), [1, 2]);
+        assert_eq!(
+            ids(json!({ "type": "StartsWith", "key": "name", "value": "Ali" })),
+            [1, 3]
+        );
+        assert_eq!(
+            ids(json!({ "type": "EndsWith", "key": "email", "value": ".com" })),
+            [1]
+        );
+        assert_eq!(
+            ids(json!({ "type": "Regex", "key": "name", "pattern": "^[a-z]" })),
+            [2]
+        );
+        assert_eq!(
+            ids(json!({ "type": "ILike", "key": "name", "pattern": "B%" })),
+            [2]
+        );
+        assert_eq!(
+            ids(json!({ "type": "Like", "key": "email", "pattern": "%.com" })),
+            [1]
+        );
+        assert!(ids(json!({ "type": "Regex", "key": "name", "pattern": "(" })).is_empty());
+        let invalid: FilterType = serde_json::from_value(json!({
+            "type": "Not",
+            "inner": { "type": "Regex", "key": "name", "pattern": "(" }
+        }))
+        .unwrap();
+        assert!(invalid.validate().is_err());
+        let valid: FilterType =
+            serde_json::from_value(json!({ "type": "ILike", "key": "name", "pattern": "b%" }))
+                .unwrap();
+        assert!(valid.validate().is_ok());
+        assert_eq!(
+            serde_json::to_value(&valid).unwrap(),
+            json!({ "type": "ILike", "key": "name", "pattern": "b%" })
+        );
+    }
+
+    #[test]
+    fn test_type_aware_comparisons() {
+        let days_ago = |days| (Utc::now() - TimeDelta::days(days)).to_rfc3339();
+        let data = [
+            json!({ "id": 1, "name": "Carol", "score": 10, "created_at": days_ago(1) }),
+            json!({ "id": 2, "name": "alice", "score": "12", "created_at": days_ago(10) }),
+            json!({ "id": 3, "name": "Bob", "score": 7.5, "created_at": "2020-01-01" }),
+            json!({ "id": 4, "score": null }),
+        ];
+        let ids = |filter: Value| matching(&data, filter);
+
+        let recent =
+            json!({ "type": "GreaterThanOrEqualsTo", "key": "created_at", "value": "now-7d" });
+        assert_eq!(ids(recent), [1]);
+        let range = json!({
+            "type": "Between",
+            "key": "created_at",
+            "from": "2019-12-31",
+            "to": "2020-01-01T12:00:00Z"
+        });
+        assert_eq!(ids(range), [3]);
+        // strings compare with strings, numbers with numbers, and null with nothing
+        assert_eq!(
+            ids(json!({ "type": "LessThanThan", "key": "name", "value": "a" })),
+            [1, 3]
+        );
+        assert_eq!(
+            ids(json!({ "type": "GreaterThan", "key": "score", "value": 8 })),
+            [1]
+        );
+        assert_eq!(
+            ids(json!({ "type": "Equals", "key": "score", "value": 10.0 })),
+            [1]
+        );
+        assert!(ids(json!({ "type": "LessThanThan", "key": "missing", "value": 1 })).is_empty());
+        assert_eq!(
+            ids(json!({ "type": "InSet", "key": "name", "value": ["Bob", "Dan"] })),
+            [3]
+        );
+        assert_eq!(
+            ids(json!({ "type": "NotInSet", "key": "name", "value": ["Bob"] })),
+            [1, 2]
+        );
+    }
+
+    #[test]
+    fn test_array_operators() {
+        let data = [
+            json!({
+                "id": 1,
+                "roles": ["admin", "editor"],
+                "items": [{ "sku": "A", "qty": 1 }, { "sku": "B", "qty": 5 }]
+            }),
+            json!({ "id": 2, "roles": ["editor"], "items": [{ "sku": "A", "qty": 3 }] }),
+            json!({ "id": 3, "roles": [], "items": "none" }),
+        ];
+        let ids = |filter: Value| matching(&data, filter);
+
+        assert_eq!(
+            ids(json!({ "type": "ArrayContains", "key": "roles", "value": "editor" })),
+            [1, 2]
+        );
+        assert_eq!(
+            ids(json!({ "type": "ArrayContainsAny", "key": "roles", "value": ["admin", "x"] })),
+            [1]
+        );
+        assert_eq!(
+            ids(
+                json!({ "type": "ArrayContainsAll", "key": "roles", "value": ["editor", "admin"] })
+            ),
+            [1]
+        );
+        assert_eq!(
+            ids(json!({ "type": "ArrayLength", "key": "roles", "value": 0 })),
+            [3]
+        );
+        assert_eq!(
+            ids(json!({ "type": "ArrayLength", "key": "roles", "op": "gte", "value": 1 })),
+            [1, 2]
+        );
+
+        // both conditions must hold for the same element
+        let elem = json!({
+            "type": "ElemMatch",
+            "key": "items",
+            "filter": {
+                "type": "And",
+                "left": { "type": "Equals", "key": "sku", "value": "A" },
+                "right": { "type": "GreaterThan", "key": "qty", "value": 2 }
+            }
+        });
+        assert_eq!(ids(elem), [2]);
+        let scalar = json!({
+            "type": "ElemMatch",
+            "key": "roles",
+            "filter": { "type": "StartsWith", "key": "", "value": "adm" }
+        });
+        assert_eq!(ids(scalar), [1]);
+    }
 }
@@ -1,5 +1,8 @@
+pub mod aggregate;
 pub mod auth;
 pub mod data;
 pub mod filter_type;
+pub mod projection;
+pub mod seed;
 pub mod sort_type;
 pub mod toml_config;
@@ -0,0 +1,344 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::utils::field_path::{Segment, segments};
+
+// ! ?select=id,name:full_name,address.city,-password
+// ! {"select": ["id", "name:full_name", "address.city"]}
+// `field` keeps a field, `a.b` keeps a nested field in its place, `field:alias`
+// keeps it under another top-level name and `-field` drops it. without any
+// kept field every field but the dropped ones is returned. paths are field
+// paths, and a plain key on a list applies to each of its elements, so
+// `items.sku` is `items[*].sku`. list elements keep their positions
+
+#[derive(Debug, Clone, PartialEq)]
+enum Selector {
+    Include { path: String, alias: Option<String> },
+    Exclude { path: String },
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(from = "SelectSpec", into = "Vec<String>")]
+pub struct Projection {
+    spec: Vec<String>,
+    selectors: Vec<Selector>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SelectSpec {
+    Fields(String),
+    List(Vec<String>),
+}
+
+impl From<SelectSpec> for Projection {
+    fn from(spec: SelectSpec) -> Self {
+        match spec {
+            SelectSpec::Fields(fields) => Projection::from_query(&fields),
+            SelectSpec::List(fields) => Projection::new(fields),
+        }
+    }
+}
+
+impl From<Projection> for Vec<String> {
+    fn from(projection: Projection) -> Self {
+        projection.spec
+    }
+}
+
+impl Projection {
+    pub fn new(fields: Vec<String>) -> Projection {
+        let spec: Vec<String> = fields
+            .into_iter()
+            .map(|field| field.trim().to_string())
+            .filter(|field| !field.is_empty())
+            .collect();
+        let selectors = spec
+            .iter()
+            .map(|field| match field.strip_prefix('-') {
+                Some(path) => Selector::Exclude {
+                    path: path.trim().to_string(),
+                },
+                None => match field.split_once(':') {
+                    Some((path, alias)) => Selector::Include {
+                        path: path.trim().to_string(),
+                        alias: Some(alias.trim().to_string()),
+                    },
+                    None => Selector::Include {
+                        path: field.clone(),
+                        alias: None,
+                    },
+                },
+            })
+            .filter(|selector| match selector {
+                Selector::Include { path, .. } | Selector::Exclude { path } => !path.is_empty(),
+            })
+            .collect();
+        Projection { spec, selectors }
+    }
+
+    pub fn from_query(value: &str) -> Projection {
+        Projection::new(value.split(',').map(str::to_string).collect())
+    }
+
+    pub fn apply(&self, row: &Value) -> Value {
+        if !row.is_object() || self.selectors.is_empty() {
+            return row.clone();
+        }
+
+        let includes = self
+            .selectors
+            .iter()
+            .any(|selector| matches!(selector, Selector::Include { .. }));
+        let mut projected = if includes {
+            Value::Object(Map::new())
+        } else {
+            row.clone()
+        };
+
+        for selector in &self.selectors {
+            match selector {
+                Selector::Include { path, alias: None } => {
+                    if let Some(value) = pick(row, &segments(row, path)) {
+                        merge(&mut projected, value);
+                    }
+                }
+                Selector::Include {
+                    path,
+                    alias: Some(alias),
+                } => {
+                    if let Some(value) = extract(row, &segments(row, path)) {
+                        projected[alias.as_str()] = value;
+                    }
+                }
+                Selector::Exclude { path } => remove(&mut projected, &segments(row, path)),
+            }
+        }
+        projected
+    }
+}
+
+// a path segment as it applies to one value: a field of an object, one list
+// element, every list element with the segment kept (a plain key, `items.sku`)
+// or used up (`items[*]`), or every field of an object
+enum Step {
+    Field(String),
+    Element(usize),
+    Each,
+    All,
+    Fields,
+}
+
+fn step(segment: &Segment, value: &Value) -> Option<Step> {
+    Some(match (segment, value) {
+        (Segment::Key(key), Value::Object(_)) => Step::Field(key.clone()),
+        (Segment::Index(i), Value::Object(_)) => Step::Field(i.to_string()),
+        (Segment::Key(key), Value::Array(_)) => match key.parse() {
+            Ok(i) => Step::Element(i),
+            Err(_) => Step::Each,
+        },
+        (Segment::Index(i), Value::Array(_)) => Step::Element(*i),
+        (Segment::Wildcard, Value::Array(_)) => Step::All,
+        (Segment::Wildcard, Value::Object(_)) => Step::Fields,
+        _ => return None,
+    })
+}
+
+// the value at `path`, lists mapped element by element
+fn extract(value: &Value, path: &[Segment]) -> Option<Value> {
+    let Some((first, rest)) = path.split_first() else {
+        return Some(value.clone());
+    };
+    let each = |values: Vec<&Value>, path: &[Segment]| {
+        Value::Array(
+            values
+                .into_iter()
+                .map(|v| extract(v, path).unwrap_or(Value::Null))
+                .collect(),
+        )
+    };
+    match (step(first, value)?, value) {
+        (Step::Field(key), Value::Object(obj)) => extract(obj.get(&key)?, rest),
+        (Step::Element(i), Value::Array(arr)) => extract(arr.get(i)?, rest),
+        (Step::Each, Value::Array(arr)) => Some(each(arr.iter().collect(), path)),
+        (Step::All, Value::Array(arr)) => Some(each(arr.iter().collect(), rest)),
+        (Step::Fields, Value::Object(obj)) => Some(each(obj.values().collect(), rest)),
+        _ => None,
+    }
+}
+
+// like `extract`, but keeps the objects and lists around the value. list
+// elements the path finds nothing in are left as holes, `{}` or null, so
+// picks of the same list line up
+fn pick(value: &Value, path: &[Segment]) -> Option<Value> {
+    let Some((first, rest)) = path.split_first() else {
+        return Some(value.clone());
+    };
+    let hole = |v: &Value| match v {
+        Value::Object(_) => Value::Object(Map::new()),
+        _ => Value::Null,
+    };
+    match (step(first, value)?, value) {
+        (Step::Field(key), Value::Object(obj)) => {
+            let inner = pick(obj.get(&key)?, rest)?;
+            Some(Value::Object(Map::from_iter([(key, inner)])))
+        }
+        (Step::Element(i), Value::Array(arr)) => {
+            let inner = pick(arr.get(i)?, rest)?;
+            let mut picked: Vec<Value> = arr[..i].iter().map(hole).collect();
+            picked.push(inner);
+            Some(Value::Array(picked))
+        }
+        (step @ (Step::Each | Step::All), Value::Array(arr)) => {
+            let path = if matches!(step, Step::Each) {
+                path
+            } else {
+                rest
+            };
+            Some(Value::Array(
+                arr.iter()
+                    .map(|v| pick(v, path).unwrap_or_else(|| hole(v)))
+                    .collect(),
+            ))
+        }
+        (Step::Fields, Value::Object(obj)) => Some(Value::Object(
+            obj.iter()
+                .filter_map(|(key, v)| Some((key.clone(), pick(v, rest)?)))
+                .collect(),
+        )),
+        _ => None,
+    }
+}
+
+// joins two picks, so `address.city` and `address.zip` end up in one object
+// and list elements are joined by position. a null never overwrites, as it
+// may be a hole
+fn merge(into: &mut Value, from: Value) {
+    match (into, from) {
+        (Value::Object(into), Value::Object(from)) => {
+            for (key, value) in from {
+                match into.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        into.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Array(into), Value::Array(from)) => {
+            let mut from = from.into_iter();
+            for (existing, value) in into.iter_mut().zip(from.by_ref()) {
+                merge(existing, value);
+            }
+            into.extend(from);
+        }
+        (_, Value::Null) => {}
+        (into, from) => *into = from,
+    }
+}
+
+fn remove(value: &mut Value, path: &[Segment]) {
+    let Some((first, rest)) = path.split_first() else {
+        return;
+    };
+    let Some(step) = step(first, value) else {
+        return;
+    };
+    match (step, value) {
+        (Step::Field(key), Value::Object(obj)) if rest.is_empty() => {
+            obj.remove(&key);
+        }
+        (Step::Field(key), Value::Object(obj)) => {
+            if let Some(inner) = obj.get_mut(&key) {
+                remove(inner, rest);
+            }
+        }
+        (Step::Element(i), Value::Array(arr)) if rest.is_empty() && i < arr.len() => {
+            arr.remove(i);
+        }
+        (Step::Element(i), Value::Array(arr)) => {
+            if let Some(inner) = arr.get_mut(i) {
+                remove(inner, rest);
+            }
+        }
+        (Step::Each, Value::Array(arr)) => arr.iter_mut().for_each(|v| remove(v, path)),
+        (Step::All, Value::Array(arr)) if rest.is_empty() => arr.clear(),
+        (Step::All, Value::Array(arr)) => arr.iter_mut().for_each(|v| remove(v, rest)),
+        (Step::Fields, Value::Object(obj)) if rest.is_empty() => obj.clear(),
+        (Step::Fields, Value::Object(obj)) => obj.values_mut().for_each(|v| remove(v, rest)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::Projection;
+
+    #[test]
+    fn test_include_exclude_and_rename() {
+        let row = json!({
+            "id": 1,
+            "name": "Alice",
+            "password": "secret",
+            "address": { "city": "Lagos", "zip": "100001", "street": "Allen" },
+            "items": [{ "sku": "A", "qty": 1 }, { "sku": "B", "qty": 2 }]
+        });
+
+        let projection =
+            Projection::from_query("id,name:full_name,address.city,address.zip,items[*].sku");
+        assert_eq!(
+            projection.apply(&row),
+            json!({
+                "id": 1,
+                "full_name": "Alice",
+                "address": { "city": "Lagos", "zip": "100001" },
+                "items": [{ "sku": "A" }, { "sku": "B" }]
+            })
+        );
+
+        let projection: Projection =
+            serde_json::from_value(json!(["-password", "-address.street", "-items.qty"])).unwrap();
+        assert_eq!(
+            projection.apply(&row),
+            json!({
+                "id": 1,
+                "name": "Alice",
+                "address": { "city": "Lagos", "zip": "100001" },
+                "items": [{ "sku": "A" }, { "sku": "B" }]
+            })
+        );
+
+        let projection = Projection::from_query("id,address.city:city,items.sku:skus");
+        assert_eq!(
+            projection.apply(&row),
+            json!({ "id": 1, "city": "Lagos", "skus": ["A", "B"] })
+        );
+    }
+
+    #[test]
+    fn test_paths_keep_list_positions() {
+        let row = json!({
+            "id": 1,
+            "tags": ["a", "b", "c"],
+            "items": [{ "sku": "A" }, { "qty": 1 }, { "sku": "B", "qty": 2 }]
+        });
+
+        let projection = Projection::from_query("items.sku,items.qty");
+        assert_eq!(
+            projection.apply(&row),
+            json!({ "items": [{ "sku": "A" }, { "qty": 1 }, { "sku": "B", "qty": 2 }] })
+        );
+        let projection = Projection::from_query("tags[0],/tags/2,items.sku:skus");
+        assert_eq!(
+            projection.apply(&row),
+            json!({ "tags": ["a", null, "c"], "skus": ["A", null, "B"] })
+        );
+        let projection = Projection::from_query("-tags[1],-/items/0,-items[*].qty");
+        assert_eq!(
+            projection.apply(&row),
+            json!({ "id": 1, "tags": ["a", "c"], "items": [{}, { "sku": "B" }] })
+        );
+    }
+}
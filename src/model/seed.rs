@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+// ! {"tables": ["users:50", "posts:200"], "seed": 7, "replace": true}
+// tables are filled in the given order, so posts can point at the new users
+
+pub const DEFAULT_SEED_COUNT: usize = 10;
+// every record is made in memory before any is written
+pub const MAX_SEED_COUNT: usize = 10_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeedInput {
+    pub tables: Vec<String>, // `table:count`, or `table` for 10 records
+    pub seed: Option<u64>,   // a random one when not given, it is returned either way
+    #[serde(default)]
+    pub replace: bool, // delete the records already in the listed tables
+}
+
+impl SeedInput {
+    /// The table names with their record counts, at most `MAX_SEED_COUNT`
+    /// records in all.
+    pub fn counts(&self) -> Result<Vec<(String, usize)>, AppError> {
+        let counts = self
+            .tables
+            .iter()
+            .map(|spec| {
+                let (table, count) = match spec.split_once(':') {
+                    Some((table, count)) => {
+                        let count = count.trim().parse().map_err(|_| {
+                            AppError::BadRequest(format!("Invalid record count in {}", spec))
+                        })?;
+                        (table.trim(), count)
+                    }
+                    None => (spec.trim(), DEFAULT_SEED_COUNT),
+                };
+                if table.is_empty() {
+                    return Err(AppError::BadRequest(format!("Missing table in {}", spec)));
+                }
+                Ok((table.to_string(), count))
+            })
+            .collect::<Result<Vec<(String, usize)>, AppError>>()?;
+        let total = counts
+            .iter()
+            .fold(0usize, |total, (_, count)| total.saturating_add(*count));
+        if total > MAX_SEED_COUNT {
+            return Err(AppError::BadRequest(format!(
+                "At most {} records can be seeded at once",
+                MAX_SEED_COUNT
+            )));
+        }
+        Ok(counts)
+    }
+}
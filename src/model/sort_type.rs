@@ -1,11 +1,211 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::utils::compare::compare_values;
+use crate::utils::field_path::resolve_first;
+
 // ! {"type": "OrderBy", "key": "name"}
+// ! [{"type": "OrderDescending", "key": "age", "nulls": "first"}, {"type": "OrderBy", "key": "name"}]
+// ! {"type": "OrderBy", "key": "address.city"}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NullsOrder {
+    First,
+    #[default]
+    Last,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum SortType {
-    OrderBy { key: Value },
-    OrderDescending { key: bool },
+    OrderBy {
+        key: Value,
+        #[serde(default)]
+        nulls: NullsOrder,
+    },
+    OrderDescending {
+        key: Value,
+        #[serde(default)]
+        nulls: NullsOrder,
+    },
+}
+
+/// A single sort key or a list of them, applied left to right.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SortBy {
+    Single(SortType),
+    Multiple(Vec<SortType>),
+}
+
+impl SortBy {
+    pub fn as_slice(&self) -> &[SortType] {
+        match self {
+            SortBy::Single(sort) => std::slice::from_ref(sort),
+            SortBy::Multiple(sorts) => sorts,
+        }
+    }
+}
+
+impl SortBy {
+    // ! ?_sort=-created_at,name
+    // a leading `-` sorts that key descending
+    pub fn from_query(value: &str) -> SortBy {
+        let sorts = value
+            .split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(|key| match key.strip_prefix('-') {
+                Some(key) => SortType::OrderDescending {
+                    key: Value::String(key.to_string()),
+                    nulls: NullsOrder::default(),
+                },
+                None => SortType::OrderBy {
+                    key: Value::String(key.to_string()),
+                    nulls: NullsOrder::default(),
+                },
+            })
+            .collect();
+        SortBy::Multiple(sorts)
+    }
+}
+
+impl SortType {
+    pub fn compare(&self, a: &Value, b: &Value) -> Ordering {
+        use SortType::*;
+
+        let (key, nulls, descending) = match self {
+            OrderBy { key, nulls } => (key, nulls, false),
+            OrderDescending { key, nulls } => (key, nulls, true),
+        };
+
+        // missing fields sort like nulls, and nulls keep their place whatever the direction.
+        // a path that resolves to several values sorts by the first one
+        fn field<'a>(row: &'a Value, key: &Value) -> Option<&'a Value> {
+            key.as_str()
+                .and_then(|k| resolve_first(row, k))
+                .filter(|v| !v.is_null())
+        }
+
+        match (field(a, key), field(b, key)) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => match nulls {
+                NullsOrder::First => Ordering::Less,
+                NullsOrder::Last => Ordering::Greater,
+            },
+            (Some(_), None) => match nulls {
+                NullsOrder::First => Ordering::Greater,
+                NullsOrder::Last => Ordering::Less,
+            },
+            (Some(x), Some(y)) if descending => compare_values(y, x),
+            (Some(x), Some(y)) => compare_values(x, y),
+        }
+    }
+}
+
+/// Stable multi-key sort: rows equal on every key keep their stored order.
+pub fn sort_rows<T: Borrow<Value>>(rows: &mut [T], sorts: &[SortType]) {
+    if sorts.is_empty() {
+        return;
+    }
+    rows.sort_by(|a, b| {
+        sorts
+            .iter()
+            .map(|sort| sort.compare(a.borrow(), b.borrow()))
+            .find(|ord| *ord != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use super::{SortBy, sort_rows};
+
+    fn ids(rows: &[Value]) -> Vec<i64> {
+        rows.iter().map(|row| row["id"].as_i64().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_multi_key() {
+        let mut data = vec![
+            json!({ "id": 1, "name": "Bob", "age": 25 }),
+            json!({ "id": 2, "name": "Alice", "age": 31 }),
+            json!({ "id": 3, "name": "Alice", "age": 29 }),
+            json!({ "id": 4, "name": "alice", "age": 9 }),
+        ];
+        let sort: SortBy = serde_json::from_value(json!([
+            { "type": "OrderBy", "key": "name" },
+            { "type": "OrderDescending", "key": "age" }
+        ]))
+        .unwrap();
+        sort_rows(&mut data, sort.as_slice());
+
+        assert_eq!(ids(&data), vec![2, 3, 1, 4]);
+    }
+
+    #[test]
+    fn test_numbers_dates_and_nulls() {
+        let mut data = vec![
+            json!({ "id": 1, "age": 100, "created_at": "2024-03-01T10:00:00+02:00" }),
+            json!({ "id": 2, "age": null, "created_at": "2024-03-01T09:00:00Z" }),
+            json!({ "id": 3, "age": 9 }),
+            json!({ "id": 4, "age": 20.5, "created_at": "2024-02-28" }),
+        ];
+
+        let sort: SortBy =
+            serde_json::from_value(json!({ "type": "OrderBy", "key": "age" })).unwrap();
+        sort_rows(&mut data, sort.as_slice());
+        assert_eq!(ids(&data), vec![3, 4, 1, 2]);
+
+        let sort: SortBy = serde_json::from_value(
+            json!({ "type": "OrderDescending", "key": "created_at", "nulls": "first" }),
+        )
+        .unwrap();
+        sort_rows(&mut data, sort.as_slice());
+        assert_eq!(ids(&data), vec![3, 2, 1, 4]);
+    }
+
+    #[test]
+    fn test_mixed_dates_and_text() {
+        let values = [
+            "2024-01-01T10:00:00+05:00",
+            "2024-01-01T06:30:00Z",
+            "2024-01-01T07",
+            "2024-01-01",
+            "2023-12-31",
+            "2024-01-01T05:00:00Z",
+            "abc",
+            "2024-01-01 00:00:00",
+            "1999",
+        ];
+        let mut data: Vec<Value> = (0..400)
+            .map(|i| json!({ "id": i, "at": values[(i * 7 + i / 9) as usize % values.len()] }))
+            .collect();
+        let sort: SortBy =
+            serde_json::from_value(json!({ "type": "OrderBy", "key": "at" })).unwrap();
+        sort_rows(&mut data, sort.as_slice());
+
+        let sorted: Vec<&str> = data.iter().map(|row| row["at"].as_str().unwrap()).collect();
+        assert_eq!(sorted.first(), Some(&"2023-12-31"));
+        assert_eq!(sorted.last(), Some(&"abc"));
+        // the naive midnight and the plain date are the same moment, the text breaks the tie
+        let midnight = sorted.iter().position(|v| *v == "2024-01-01").unwrap();
+        let naive = sorted
+            .iter()
+            .position(|v| *v == "2024-01-01 00:00:00")
+            .unwrap();
+        assert!(midnight < naive);
+        let first_text = sorted.iter().position(|v| *v == "1999").unwrap();
+        assert!(
+            sorted[first_text..]
+                .iter()
+                .all(|v| ["1999", "2024-01-01T07", "abc"].contains(v))
+        );
+    }
 }
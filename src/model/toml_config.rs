@@ -1,4 +1,7 @@
+use std::collections::{BTreeMap, HashMap};
+
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "lowercase")]
@@ -7,10 +10,21 @@ pub enum AuthType {
     Session,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageType {
+    #[default]
+    Json,
+    Ndjson,
+    Sqlite,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AppConfig {
     pub overview: Overview,
     pub config: Config,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tables: HashMap<String, TableConfig>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -23,4 +37,98 @@ pub struct Overview {
 pub struct Config {
     pub port: u16,
     pub auth: Option<AuthType>,
+    pub flush_interval_ms: Option<u64>, // how often changed tables are written to disk, 500 by default
+    pub storage: Option<StorageType>,   // json by default
+    pub envelope: Option<bool>, // wrap lists in {data, total, limit, offset}, false by default
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum IdStrategy {
+    #[default]
+    AutoIncrement,
+    UuidV4,
+    UuidV7,
+    Ulid,
+    Client, // the client sends the id with every new record
+}
+
+// ! [tables.users]
+// ! primary_key = "_id"
+// ! id_strategy = "uuid_v4"
+// ! timestamps = true
+// ! soft_delete = true
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TableConfig {
+    pub primary_key: Option<String>, // "id" by default
+    pub id_strategy: Option<IdStrategy>,
+    pub timestamps: Option<bool>, // keep `created_at` and `updated_at` on every record, false by default
+    pub soft_delete: Option<bool>, // DELETE sets `deleted_at` instead of removing, false by default
+    pub search_fields: Option<Vec<String>>, // fields searched by ?q=, every string field by default
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub relations: HashMap<String, Relation>, // named links to other tables, see `Relation`
+    pub schema: Option<Value>, // JSON Schema for the records, `_schemas/{table}.schema.json` otherwise
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fake: BTreeMap<String, String>, // field hints for `seed`, e.g. `role = "enum:admin,member"`
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RelationKind {
+    BelongsTo,  // this record holds the key of one record of `table`
+    HasMany,    // records of `table` hold the key of this record
+    ManyToMany, // rows of the `through` table pair this record with records of `table`
+}
+
+// ! [tables.posts.relations.author]
+// ! type = "belongs_to"
+// ! table = "users"
+// ! foreign_key = "authorId"
+// ! on_delete = "cascade"
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Relation {
+    #[serde(rename = "type")]
+    pub kind: RelationKind,
+    pub table: String,
+    pub foreign_key: Option<String>, // "{name}Id" on this table for belongs_to, "{this table singular}Id" otherwise
+    pub through: Option<String>,     // the join table of a many_to_many
+    pub other_key: Option<String>, // the join table field pointing at `table`, "{table singular}Id" by default
+    pub on_delete: Option<OnDelete>, // belongs_to only, what deleting the `table` record does to this one
+}
+
+// a declared belongs_to is a foreign key: its value must be the key of an
+// existing record, and deleting that record is handled by `on_delete`
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OnDelete {
+    #[default]
+    Restrict, // the delete fails with 409 while records still point at it
+    Cascade, // the pointing records are deleted as well
+    SetNull, // the key of the pointing records is set to null
+}
+
+impl TableConfig {
+    pub fn primary_key(&self) -> &str {
+        self.primary_key.as_deref().unwrap_or("id")
+    }
+
+    pub fn id_strategy(&self) -> IdStrategy {
+        self.id_strategy.unwrap_or_default()
+    }
+
+    pub fn timestamps(&self) -> bool {
+        self.timestamps.unwrap_or(false)
+    }
+
+    pub fn soft_delete(&self) -> bool {
+        self.soft_delete.unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+impl TableConfig {
+    /// A table config as written under `[tables.x]`, for tests.
+    pub fn from_toml(toml: &str) -> TableConfig {
+        toml::from_str(toml).unwrap()
+    }
 }
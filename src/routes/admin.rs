@@ -0,0 +1,25 @@
+use axum::{
+    Json, Router,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing::post,
+};
+use serde_json::Value;
+
+use crate::{
+    AppState, helpers::seed::seed_tables, model::seed::SeedInput, routes::auth::authorize,
+};
+
+pub fn admin_router(config: AppState) -> Router<AppState> {
+    Router::new().route("/seed", post(seed)).with_state(config)
+}
+
+// ! POST /_admin/seed {"tables": ["users:50", "posts:200"], "seed": 7}
+async fn seed(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(input): Json<SeedInput>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    authorize(&state, &headers)?;
+    Ok(Json(seed_tables(&state.store, &input)?))
+}
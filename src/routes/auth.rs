@@ -5,7 +5,7 @@ use argon2::{
 use axum::{
     Form, Json, Router,
     extract::State,
-    http::{HeaderMap, StatusCode},
+    http::{HeaderMap, StatusCode, header::AUTHORIZATION},
     routing::{get, post},
 };
 use chrono::{Duration, Utc};
@@ -15,14 +15,12 @@ use serde_json::{Value, json};
 use crate::{
     AppState,
     consts::{AUTH_TABLE_NAME, KEYS},
-    helpers::{
-        crud::{create_data, delete_data},
-        json::read_json,
-    },
+    helpers::crud::{create_data, delete_data, find_data, id_string},
     model::{
-        auth::{AuthBody, Claims, SignInInput, SignUpInput},
+        auth::{Claims, SignInInput, SignUpInput},
         toml_config::AuthType,
     },
+    utils::decode_jwt::decode_jwt,
 };
 
 pub fn auth_router(config: AppState) -> Router<AppState> {
@@ -33,11 +31,44 @@ pub fn auth_router(config: AppState) -> Router<AppState> {
         .with_state(config)
 }
 
+/// Checks the bearer token or session id the configured auth asks for, a
+/// server without auth lets everything through.
+pub fn authorize(state: &AppState, headers: &HeaderMap) -> Result<(), (StatusCode, Json<Value>)> {
+    let unauthorized = || {
+        Err((
+            StatusCode::UNAUTHORIZED,
+            Json(json!({"message":"Unauthorized"})),
+        ))
+    };
+    match state.config.auth {
+        Some(AuthType::Jwt) => match headers.get(AUTHORIZATION).and_then(|v| v.to_str().ok()) {
+            Some(value) if decode_jwt(value) => Ok(()),
+            Some(_) => unauthorized(),
+            None => Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({"message":"No Authorization header found"})),
+            )),
+        },
+        Some(AuthType::Session) => match headers.get("x-session").and_then(|v| v.to_str().ok()) {
+            Some(session) if find_data(&state.store, "session", session).is_some() => Ok(()),
+            Some(_) => unauthorized(),
+            None => Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({"message":"No Session Id found"})),
+            )),
+        },
+        None => Ok(()),
+    }
+}
+
 pub async fn root() -> &'static str {
     "Hello, World!"
 }
 
-async fn sign_up(Form(input): Form<SignUpInput>) -> Json<Value> {
+async fn sign_up(
+    State(state): State<AppState>,
+    Form(input): Form<SignUpInput>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
     let argon2 = Argon2::default();
     let salt = SaltString::generate(&mut OsRng);
     let password_hash = argon2
@@ -46,50 +77,46 @@ async fn sign_up(Form(input): Form<SignUpInput>) -> Json<Value> {
         .to_string();
 
     let mut res = create_data(
+        &state.store,
         AUTH_TABLE_NAME,
         json!({
             "name":input.name,
             "email": input.email,
             "password_hash": password_hash
         }),
-    );
+    )?;
 
     if let Some(auth) = res.as_object_mut() {
         auth.remove("password_hash");
         //auth["id"] = Value::Null;
     }
-    return Json(res);
+    Ok(Json(res))
 }
 
 async fn sign_in(
     State(state): State<AppState>,
     Form(input): Form<SignInInput>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    let mut data = read_json(AUTH_TABLE_NAME);
+    let user = state.store.read(AUTH_TABLE_NAME, |arr| {
+        arr.iter()
+            .find(|obj| obj.get("email") == Some(&Value::String(input.email.clone())))
+            .cloned()
+    });
 
-    let mut email_exist = false;
-    let mut user_id: Option<i64> = None;
-    let mut hashed_password: Option<&str> = None;
-    if let Value::Array(arr) = &mut data {
-        for obj in arr.iter_mut() {
-            if obj.get("email") == Some(&Value::String(input.email.clone())) {
-                email_exist = true;
-                user_id = obj["id"].as_i64();
-                hashed_password = obj["password_hash"].as_str();
-                break;
-            }
-        }
-    }
+    let email_exist = user.is_some();
+    let key = state.store.config(AUTH_TABLE_NAME).primary_key();
+    let user_id: Option<Value> = user.as_ref().and_then(|obj| obj.get(key)).cloned();
+    let hashed_password: Option<&str> = user.as_ref().and_then(|obj| obj["password_hash"].as_str());
 
-    if email_exist == true {
+    if email_exist {
         let hash = hashed_password.unwrap();
         let parsed_hash = PasswordHash::new(hash);
-        if let Err(e) = parsed_hash {
+        if parsed_hash.is_err() {
             return Err((StatusCode::BAD_REQUEST, Json(json!({"message":""}))));
         }
-        if !Argon2::default()
+        if Argon2::default()
             .verify_password(input.password.as_bytes(), &parsed_hash.unwrap())
-            .is_ok()
+            .is_err()
         {
             return Err((
                 StatusCode::BAD_REQUEST,
@@ -104,7 +131,7 @@ async fn sign_in(
                     let exp_time = now + Duration::days(7).num_seconds() as usize;
                     let issuer = "aginisi.com".to_string();
                     let claims = Claims {
-                        sub: user_id.unwrap(),
+                        sub: id_string(user_id.as_ref()).unwrap_or_default(),
                         exp: exp_time,
                         iss: issuer,
                         iat: now,
@@ -124,11 +151,12 @@ async fn sign_in(
                 }
                 AuthType::Session => {
                     let res = create_data(
+                        &state.store,
                         "session",
                         json!({
-                            "user_id":user_id.unwrap()
+                            "user_id": user_id
                         }),
-                    );
+                    )?;
                     return Ok(Json(res));
                 }
             }
@@ -144,15 +172,14 @@ pub async fn sign_out(
     State(state): State<AppState>,
     headers: HeaderMap,
 ) -> Result<(), (StatusCode, Value)> {
-    if let Some(a) = state.config.auth {
-        if a == AuthType::Session {
-            if let Some(header) = headers.get("x-session").and_then(|v| v.to_str().ok()) {
-                let id = header.parse::<u64>().unwrap();
-                delete_data("session", id);
-                return Ok(());
-            }
-        }
+    if state.config.auth == Some(AuthType::Session)
+        && let Some(header) = headers.get("x-session").and_then(|v| v.to_str().ok())
+    {
+        return delete_data(&state.store, "session", header).map_err(|err| {
+            let (status, Json(body)) = err.into();
+            (status, body)
+        });
     }
 
-    return Err((StatusCode::UNAUTHORIZED, json!({"message":"Unauthorized"})));
+    Err((StatusCode::UNAUTHORIZED, json!({"message":"Unauthorized"})))
 }
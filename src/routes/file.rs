@@ -1,21 +1,19 @@
 use std::{fs::File, io::Write};
 
 use axum::{
-    Router,
+    Json, Router,
     body::Body,
-    extract::{Multipart, Path},
+    extract::{Multipart, Path, State},
     http::StatusCode,
     response::{IntoResponse, Response},
     routing::{get, post},
 };
-use serde_json::json;
+use serde_json::{Value, json};
 use tokio_util::io::ReaderStream;
 
 use tokio::fs::File as TokioFile;
 
-use crate::{
-    AppState, consts::UPLOAD_FOLDER_NAME, helpers::crud::create_data, model::toml_config::Config,
-};
+use crate::{AppState, consts::UPLOAD_FOLDER_NAME, helpers::crud::create_data};
 
 pub fn file_router(config: AppState) -> Router<AppState> {
     Router::new()
@@ -29,7 +27,7 @@ async fn root() -> &'static str {
     "Hello, World!"
 }
 
-async fn upload(mut multipart: Multipart) -> impl IntoResponse {
+async fn upload(State(state): State<AppState>, mut multipart: Multipart) -> impl IntoResponse {
     let mut name = String::new();
     while let Some(field) = multipart.next_field().await.unwrap() {
         let file_name = field.file_name().unwrap_or("upload.bin").to_string();
@@ -39,13 +37,16 @@ async fn upload(mut multipart: Multipart) -> impl IntoResponse {
         let mut file = File::create(format!("{}/{}", UPLOAD_FOLDER_NAME, file_name)).unwrap();
         file.write_all(&data).unwrap();
     }
-    create_data(
+    if let Err(err) = create_data(
+        &state.store,
         "file",
         json!({
             "file_name": name,
         }),
-    );
-    StatusCode::OK
+    ) {
+        return <(StatusCode, Json<Value>)>::from(err).into_response();
+    }
+    StatusCode::OK.into_response()
 }
 
 async fn download(Path(file_name): Path<String>) -> impl IntoResponse {
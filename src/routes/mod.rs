@@ -1,203 +1,601 @@
+pub mod admin;
 pub mod auth;
 pub mod file;
 
 use std::collections::HashMap;
-use std::fs;
 
 use axum::Json;
+use axum::body::Bytes;
 use axum::extract::Path as RoutePath;
 use axum::extract::Query;
-use axum::extract::Request;
+use axum::extract::RawQuery;
 use axum::extract::State;
 use axum::http::HeaderMap;
+use axum::http::HeaderValue;
 use axum::http::Method;
 use axum::http::StatusCode;
-use axum::http::header::AUTHORIZATION;
-use axum::response::IntoResponse;
+use axum::http::header::{CONTENT_TYPE, LINK};
+use axum::response::{IntoResponse, Response};
 use serde_json::{Value, json};
-use socketioxide::extract::{Data as SData, SocketRef};
-use tracing::info;
 
 use crate::AppState;
+use crate::consts::DELETED_AT;
+use crate::error::AppError;
 use crate::helpers::crud::create_data;
+use crate::helpers::crud::create_many;
 use crate::helpers::crud::delete_data;
+use crate::helpers::crud::delete_where;
+use crate::helpers::crud::find_data;
+use crate::helpers::crud::is_deleted;
+use crate::helpers::crud::patch_data;
+use crate::helpers::crud::replace_data;
+use crate::helpers::crud::restore_data;
 use crate::helpers::crud::update_data;
-use crate::helpers::json::read_json;
+use crate::helpers::crud::update_where;
+use crate::helpers::relations::{child_key, include_related, parse_includes};
+use crate::model::aggregate::Aggregate;
 use crate::model::data::Data;
-use crate::model::toml_config::AuthType;
-use crate::model::toml_config::Config;
-use crate::utils::decode_jwt::decode_jwt;
-
-pub async fn root() -> Json<Value> {
-    let entries = fs::read_dir("aginisi").unwrap();
-    let mut names = Vec::<String>::new();
-    for entry in entries {
-        let entry = entry.unwrap();
-        let path = entry.path();
-
-        if path.is_file() {
-            if let Some(name) = path.file_name().and_then(|t| t.to_str()) {
-                names.push(name.strip_suffix(".json").unwrap().to_string());
-            }
+use crate::model::filter_type::FilterType;
+use crate::model::projection::Projection;
+use crate::model::sort_type::{SortBy, sort_rows};
+use crate::routes::auth::authorize;
+use crate::utils::filter_parser::parse_where;
+use crate::utils::search::Search;
+
+pub async fn root(State(state): State<AppState>) -> Json<Value> {
+    Json(json!(state.store.names()))
+}
+
+fn parse_param(
+    params: &HashMap<String, String>,
+    name: &str,
+    default: usize,
+) -> Result<usize, (StatusCode, Json<Value>)> {
+    match params.get(name) {
+        Some(value) => value.parse().map_err(|_| {
+            AppError::BadRequest(format!("{} must be a positive number", name)).into()
+        }),
+        None => Ok(default),
+    }
+}
+
+// ! pagination headers
+// X-Total-Count carries the number of rows after filtering and Link points at
+// the first, previous, next and last pages with the rest of the query kept
+struct Page<'a> {
+    path: &'a str,
+    query: Option<&'a str>,
+    total: usize,
+    limit: usize,
+    offset: usize,
+}
+
+impl Page<'_> {
+    fn url(&self, offset: usize) -> String {
+        let mut query: Vec<String> = self
+            .query
+            .unwrap_or("")
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .filter(|pair| !pair.starts_with("offset=") && !pair.starts_with("limit="))
+            .map(|pair| pair.to_string())
+            .collect();
+        query.push(format!("limit={}", self.limit));
+        query.push(format!("offset={}", offset));
+        format!("/{}?{}", self.path, query.join("&"))
+    }
+
+    fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-total-count", HeaderValue::from(self.total));
+
+        if self.limit == 0 {
+            return headers;
+        }
+        let last = self.total.saturating_sub(1) / self.limit * self.limit;
+        let mut links = vec![format!("<{}>; rel=\"first\"", self.url(0))];
+        if self.offset > 0 {
+            let prev = self.offset.saturating_sub(self.limit).min(last);
+            links.push(format!("<{}>; rel=\"prev\"", self.url(prev)));
+        }
+        if self.offset + self.limit < self.total {
+            links.push(format!(
+                "<{}>; rel=\"next\"",
+                self.url(self.offset + self.limit)
+            ));
+        }
+        links.push(format!("<{}>; rel=\"last\"", self.url(last)));
+
+        if let Ok(link) = HeaderValue::from_str(&links.join(", ")) {
+            headers.insert(LINK, link);
+        }
+        headers
+    }
+}
+
+// the body is optional so plain GET requests can describe everything in the
+// query string, but one that is sent must be JSON. a JSON Patch document is
+// the bare list of operations
+fn read_body(headers: &HeaderMap, body: &[u8]) -> Result<Data, AppError> {
+    if body.is_empty() {
+        return Ok(Data::default());
+    }
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .unwrap_or("")
+        .trim();
+    if !(content_type == "application/json" || content_type.ends_with("+json")) {
+        return Err(AppError::UnsupportedMediaType(
+            "Expected request with `Content-Type: application/json`".to_string(),
+        ));
+    }
+    let invalid = |e: serde_json::Error| AppError::BadRequest(format!("Invalid body: {}", e));
+    if content_type == "application/json-patch+json" {
+        let document: Value = serde_json::from_slice(body).map_err(invalid)?;
+        if document.is_array() {
+            return Ok(Data {
+                data: Some(document),
+                ..Data::default()
+            });
         }
+        return serde_json::from_value(document).map_err(invalid);
     }
-    Json(json!(names))
+    serde_json::from_slice(body).map_err(invalid)
 }
 
-//impl IntoResponse
 pub async fn f_route(
     State(state): State<AppState>,
     headers: HeaderMap,
     method: Method,
     RoutePath(path): RoutePath<String>,
     Query(params): Query<HashMap<String, String>>,
-    Json(data): Json<Data>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    RawQuery(raw_query): RawQuery,
+    body: Bytes,
+) -> Result<Response, (StatusCode, Json<Value>)> {
     let io = state.socket_io.clone();
     let post_to_socket_io = |data: Value, path: String| async move {
         io.emit(format!("{}-listener", path), &data).await.unwrap();
         data
     };
 
-    if let Some(e) = state.config.auth {
-        match e {
-            AuthType::Jwt => match headers.get(AUTHORIZATION).and_then(|v| v.to_str().ok()) {
-                Some(value) => {
-                    if !decode_jwt(value) {
-                        return Err((
-                            StatusCode::UNAUTHORIZED,
-                            Json(json!({"message":"Unauthorized"})),
-                        ));
-                    }
-                }
-                None => {
-                    return Err((
-                        StatusCode::BAD_REQUEST,
-                        Json(json!({"message":"No Authorization header found"})),
-                    ));
+    authorize(&state, &headers)?;
+
+    let mut data = read_body(&headers, &body)?;
+    // the body filter, `where` expressions and query filters must all match
+    let mut filters: Vec<FilterType> = data.filter.take().into_iter().collect();
+    for expression in [data.r#where.take(), params.get("where").cloned()]
+        .into_iter()
+        .flatten()
+    {
+        let filter = parse_where(&expression)
+            .map_err(|e| AppError::BadRequest(format!("Invalid where: {}", e)))?;
+        filters.push(filter);
+    }
+    filters.extend(FilterType::from_query(&params));
+    data.filter = FilterType::all(filters);
+    if let Some(filter) = &data.filter {
+        filter.validate().map_err(AppError::BadRequest)?;
+    }
+    if let Some(sort) = params.get("_sort") {
+        data.sort = Some(SortBy::from_query(sort));
+    }
+    let projection = params
+        .get("select")
+        .or(params.get("_fields"))
+        .map(|f| Projection::from_query(f))
+        .or(data.select.take());
+    let includes = parse_includes(
+        ["_expand", "_embed"]
+            .iter()
+            .filter_map(|param| params.get(*param))
+            .map(String::as_str),
+    )?;
+    let project = |row: Value| match &projection {
+        Some(projection) => projection.apply(&row),
+        None => row,
+    };
+
+    let mut segments: Vec<String> = path.split('/').map(str::to_string).collect();
+    // POST /{table}/{id}/_restore brings back a soft deleted record
+    if let [table, id, action] = segments.as_slice()
+        && action == "_restore"
+    {
+        if method != Method::POST {
+            return Err((StatusCode::FORBIDDEN, Json(json!({}))));
+        }
+        let res = restore_data(&state.store, table, id)?;
+        let res = post_to_socket_io(res, table.clone()).await;
+        return Ok(Json(project(res)).into_response());
+    }
+    // /{parent}/{id}/{child} is the child table narrowed to the records of that
+    // parent, and records created there get the parent's key
+    if let [parent, id, child] = segments.as_slice() {
+        if !state.store.names().contains(parent) {
+            return Err(AppError::NotFound.into());
+        }
+        let parent_record = find_data(&state.store, parent, id)
+            .filter(|record| !is_deleted(state.store.config(parent), record))
+            .ok_or(AppError::NotFound)?;
+        let parent_id = parent_record[state.store.config(parent).primary_key()].clone();
+        let key = child_key(&state.store, parent, child);
+
+        // the parent's key narrows bulk PATCH and DELETE, it does not stand in for their filter
+        if matches!(method, Method::PATCH | Method::DELETE) && data.filter.is_none() {
+            return Err(AppError::BadRequest(format!(
+                "{} on a nested table expects a filter",
+                method
+            ))
+            .into());
+        }
+        let owned = FilterType::Equals {
+            key: json!(key),
+            value: parent_id.clone(),
+        };
+        data.filter = FilterType::all(data.filter.take().into_iter().chain([owned]).collect());
+        if method == Method::POST {
+            match &mut data.data {
+                Some(Value::Object(item)) => {
+                    item.insert(key, parent_id);
                 }
-            },
-            AuthType::Session => match headers.get("x-session").and_then(|v| v.to_str().ok()) {
-                Some(session) => {
-                    if let Some(values) = read_json("session").as_array() {
-                        let id = session.parse::<u64>().unwrap();
-                        let mut authorized = false;
-                        for a in values.iter() {
-                            if a.get("id") == Some(&Value::Number(id.into())) {
-                                authorized = true;
-                                break;
-                            }
-                        }
-                        if !authorized {
-                            return Err((
-                                StatusCode::UNAUTHORIZED,
-                                Json(json!({"message":"Unauthorized"})),
-                            ));
-                        }
+                Some(Value::Array(items)) => {
+                    for item in items.iter_mut().filter_map(Value::as_object_mut) {
+                        item.insert(key.clone(), parent_id.clone());
                     }
                 }
-                None => {
-                    return Err((
-                        StatusCode::BAD_REQUEST,
-                        Json(json!({"message":"No Session Id found"})),
-                    ));
-                }
-            },
+                _ => {}
+            }
         }
+        segments = vec![child.clone()];
     }
+    let split_part = || segments.iter().map(String::as_str).collect::<Vec<&str>>();
 
-    let split_part = || {
-        let mut b = path.rsplit("/").collect::<Vec<&str>>();
-        b.reverse();
-        b
+    // soft deleted records are hidden unless `include_deleted` or `only_deleted` asks for them
+    let flag = |name: &str| params.get(name).is_some_and(|v| v == "true");
+    let deleted = FilterType::IsNull {
+        key: json!(DELETED_AT),
+    };
+    let visible = match segments.first() {
+        Some(table) if state.store.config(table).soft_delete() => {
+            if flag("only_deleted") {
+                Some(FilterType::Not {
+                    inner: Box::new(deleted),
+                })
+            } else if flag("include_deleted") {
+                None
+            } else {
+                Some(deleted)
+            }
+        }
+        _ => None,
+    };
+    // bulk PATCH and DELETE still need a filter of their own
+    if let Some(visible) = &visible
+        && (method == Method::GET || data.filter.is_some())
+    {
+        data.filter = FilterType::all(
+            data.filter
+                .take()
+                .into_iter()
+                .chain([visible.clone()])
+                .collect(),
+        );
+    }
+    let hidden = |file_name: &str, id: &str| {
+        visible.as_ref().is_some_and(|visible| {
+            find_data(&state.store, file_name, id).is_some_and(|record| !visible.evaluate(&record))
+        })
     };
 
-    let res: Result<Json<Value>, (StatusCode, Json<Value>)> = match method {
+    let res: Result<Response, (StatusCode, Json<Value>)> = match method {
         Method::GET => {
+            // GET /{table}/_aggregate, or a table with something to aggregate
+            let aggregate = Aggregate::from_query(&params).or(data.aggregate.take());
+            let aggregate = match split_part().as_slice() {
+                [_, "_aggregate"] => Some(aggregate.unwrap_or_default()),
+                [_] => aggregate,
+                _ => None,
+            };
+            if let Some(aggregate) = aggregate {
+                let file_name = split_part().first().unwrap().to_string();
+                let mut groups = state.store.read(&file_name, |rows| {
+                    let rows: Vec<&Value> = match &data.filter {
+                        Some(filter) => rows.iter().filter(|row| filter.evaluate(row)).collect(),
+                        None => rows.iter().collect(),
+                    };
+                    aggregate.apply(&rows)
+                });
+                if aggregate.group_by.is_empty() {
+                    return Ok(Json(groups.pop().unwrap_or_else(|| json!({}))).into_response());
+                }
+                if let Some(sort) = &data.sort {
+                    sort_rows(&mut groups, sort.as_slice());
+                }
+                return Ok(Json(json!(groups)).into_response());
+            }
+
             if split_part().len() == 1 {
-                let limit: usize = params
-                    .get("limit")
-                    .unwrap_or(&"20".to_string())
-                    .parse()
-                    .unwrap();
-                let offset: usize = params
-                    .get("offset")
-                    .unwrap_or(&"0".to_string())
-                    .parse()
-                    .unwrap();
-                let file_name = split_part().get(0).unwrap().to_string();
-                let empty_vec: Vec<Value> = vec![];
-                let json = read_json(&file_name);
-                let json_array = json.as_array().unwrap_or(&empty_vec);
-                if let Some(filter) = data.filter {
-                    let data: Vec<_> = json_array
-                        .iter()
+                let limit = parse_param(&params, "limit", 20)?;
+                let offset = parse_param(&params, "offset", 0)?;
+                let envelope = params
+                    .get("envelope")
+                    .map(|v| v == "true")
+                    .or(state.config.envelope)
+                    .unwrap_or(false);
+                let file_name = split_part().first().unwrap().to_string();
+                let search = params
+                    .get("q")
+                    .map(|q| Search::new(q))
+                    .filter(|search| !search.is_empty());
+                let highlight = params.get("highlight").is_some_and(|v| v == "true");
+                let search_fields = state.store.config(&file_name).search_fields.as_deref();
+                let (page, total) = state.store.read(&file_name, |rows| {
+                    let mut rows: Vec<&Value> = match &data.filter {
+                        Some(filter) => rows.iter().filter(|row| filter.evaluate(row)).collect(),
+                        None => rows.iter().collect(),
+                    };
+                    // search results come best match first unless a sort is asked for
+                    if let Some(search) = &search {
+                        let mut scored: Vec<(&Value, f64)> = rows
+                            .into_iter()
+                            .filter_map(|row| Some((row, search.score(row, search_fields)?)))
+                            .collect();
+                        if data.sort.is_none() {
+                            scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+                        }
+                        rows = scored.into_iter().map(|(row, _)| row).collect();
+                    }
+                    if let Some(sort) = &data.sort {
+                        sort_rows(&mut rows, sort.as_slice());
+                    }
+                    let total = rows.len();
+                    let page: Vec<(Value, Option<Value>)> = rows
+                        .into_iter()
                         .skip(offset)
                         .take(limit)
-                        .filter(|row| filter.evaluate(row))
-                        .clone()
+                        .map(|row| {
+                            let highlights = search
+                                .as_ref()
+                                .filter(|_| highlight)
+                                .map(|search| search.highlight(row, search_fields));
+                            (row.clone(), highlights)
+                        })
                         .collect();
-                    return Ok(Json(json!(data)));
-                }
-                let data: Vec<_> = json_array.iter().skip(offset).take(limit).clone().collect();
-                return Ok(Json(json!(data)));
+                    (page, total)
+                });
+                let (mut page, highlights): (Vec<Value>, Vec<Option<Value>>) =
+                    page.into_iter().unzip();
+                include_related(&state.store, &file_name, &mut page, &includes)?;
+                let page: Vec<Value> = page
+                    .into_iter()
+                    .zip(highlights)
+                    .map(|(row, highlights)| match (project(row), highlights) {
+                        (Value::Object(mut projected), Some(highlights)) => {
+                            projected.insert("_highlight".to_string(), highlights);
+                            Value::Object(projected)
+                        }
+                        (projected, _) => projected,
+                    })
+                    .collect();
+
+                let page_info = Page {
+                    path: &path,
+                    query: raw_query.as_deref(),
+                    total,
+                    limit,
+                    offset,
+                };
+                let body = if envelope {
+                    json!({"data": page, "total": total, "limit": limit, "offset": offset})
+                } else {
+                    json!(page)
+                };
+                return Ok((page_info.headers(), Json(body)).into_response());
             } else if split_part().len() == 2 {
-                return Ok(Json(json!([])));
+                let file_name = split_part().first().unwrap().to_string();
+                let mut record = split_part()
+                    .get(1)
+                    .and_then(|id| find_data(&state.store, &file_name, id))
+                    .filter(|record| visible.as_ref().is_none_or(|v| v.evaluate(record)))
+                    .ok_or(AppError::NotFound)?;
+                include_related(
+                    &state.store,
+                    &file_name,
+                    std::slice::from_mut(&mut record),
+                    &includes,
+                )?;
+                return Ok(Json(project(record)).into_response());
             }
-            return Ok(Json(json!([])));
+            return Ok(Json(json!([])).into_response());
         }
         Method::POST => {
             if split_part().len() == 1 {
-                let file_name = split_part().get(0).unwrap().to_string();
-                if let Some(data) = data.data {
-                    let res = create_data(&file_name, data.clone());
+                let file_name = split_part().first().unwrap().to_string();
+                if let Some(Value::Array(items)) = data.data {
+                    let res = create_many(&state.store, &file_name, items)?;
+                    post_to_socket_io(json!(res), file_name).await;
+                    let res: Vec<Value> = res.into_iter().map(project).collect();
+                    return Ok(Json(json!({"count": res.len(), "data": res})).into_response());
+                } else if let Some(data) = data.data {
+                    let res = create_data(&state.store, &file_name, data.clone())?;
                     let res = post_to_socket_io(res, file_name).await;
-                    return Ok(Json(res));
+                    return Ok(Json(project(res)).into_response());
                 } else {
-                    return Ok(Json(json!({})));
+                    return Ok(Json(json!({})).into_response());
                 }
             } else {
-                return Ok(Json(json!({})));
+                return Ok(Json(json!({})).into_response());
             }
         }
         Method::PATCH => {
             if split_part().len() == 1 {
-                return Ok(Json(json!({})));
+                let file_name = split_part().first().unwrap().to_string();
+                let (Some(filter), Some(patch)) = (data.filter, data.data) else {
+                    return Err(AppError::BadRequest(
+                        "PATCH on a table expects a filter and an object in data".to_string(),
+                    )
+                    .into());
+                };
+                if !patch.is_object() {
+                    return Err(AppError::BadRequest(
+                        "PATCH on a table expects an object in data".to_string(),
+                    )
+                    .into());
+                }
+                let res = update_where(&state.store, &file_name, &filter, &patch)?;
+                let res: Vec<Value> = res.into_iter().map(project).collect();
+                return Ok(Json(json!({"count": res.len(), "data": res})).into_response());
             } else if split_part().len() == 2 {
-                let file_name = split_part().get(0).unwrap().to_string();
-                let id: u64 = split_part().get(1).unwrap().to_string().parse().unwrap();
-
-                if let Some(data) = data.data {
-                    let mut res = json!({});
-                    if data.is_object() {
-                        let data = data.as_object().unwrap();
-                        if let Some((key, value)) = data.iter().next() {
-                            let key = key;
-                            let value = value.clone();
-                            res = update_data(&file_name, id, key, value);
-                        }
+                let file_name = split_part().first().unwrap().to_string();
+                let id = split_part().get(1).unwrap().to_string();
+                if hidden(&file_name, &id) {
+                    return Err(AppError::NotFound.into());
+                }
+                let json_patch = headers
+                    .get(CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+                    .is_some_and(|v| v.starts_with("application/json-patch+json"));
+
+                let res = match data.data {
+                    Some(ops) if json_patch || ops.is_array() => {
+                        patch_data(&state.store, &file_name, &id, ops)?
+                    }
+                    Some(patch) if patch.is_object() => {
+                        update_data(&state.store, &file_name, &id, &patch)?
+                    }
+                    _ => {
+                        return Err(AppError::BadRequest(
+                            "PATCH expects an object or a list of json patch operations in data"
+                                .to_string(),
+                        )
+                        .into());
+                    }
+                };
+                return Ok(Json(project(res)).into_response());
+            } else {
+                return Ok(Json(json!({})).into_response());
+            }
+        }
+        Method::PUT => {
+            if split_part().len() == 2 {
+                let file_name = split_part().first().unwrap().to_string();
+                let id = split_part().get(1).unwrap().to_string();
+                let upsert = params.get("upsert").is_some_and(|v| v == "true");
+                if hidden(&file_name, &id) {
+                    return Err(AppError::NotFound.into());
+                }
+
+                match data.data {
+                    Some(data) if data.is_object() => {
+                        let res = replace_data(&state.store, &file_name, &id, data, upsert)?;
+                        let res = post_to_socket_io(res, file_name).await;
+                        return Ok(Json(project(res)).into_response());
+                    }
+                    _ => {
+                        return Err(AppError::BadRequest(
+                            "PUT expects an object in data".to_string(),
+                        )
+                        .into());
                     }
-                    return Ok(Json(res));
-                } else {
-                    return Ok(Json(json!({})));
                 }
             } else {
-                return Ok(Json(json!({})));
+                return Ok(Json(json!({})).into_response());
             }
         }
         Method::DELETE => {
             if split_part().len() == 1 {
-                return Ok(Json(json!({})));
+                let file_name = split_part().first().unwrap().to_string();
+                let Some(filter) = data.filter else {
+                    return Err(AppError::BadRequest(
+                        "DELETE on a table expects a filter".to_string(),
+                    )
+                    .into());
+                };
+                let res = delete_where(&state.store, &file_name, &filter)?;
+                let res: Vec<Value> = res.into_iter().map(project).collect();
+                return Ok(Json(json!({"count": res.len(), "data": res})).into_response());
             } else if split_part().len() == 2 {
-                let file_name = split_part().get(0).unwrap().to_string();
-                let id: u64 = split_part().get(1).unwrap().to_string().parse().unwrap();
-                delete_data(&file_name, id);
-                return Ok(Json(json!({})));
+                let file_name = split_part().first().unwrap().to_string();
+                let id = split_part().get(1).unwrap().to_string();
+                if hidden(&file_name, &id) {
+                    return Err(AppError::NotFound.into());
+                }
+                delete_data(&state.store, &file_name, &id)?;
+                return Ok(Json(json!({})).into_response());
             } else {
-                return Ok(Json(json!({})));
+                return Ok(Json(json!({})).into_response());
             }
         }
         _ => Err((StatusCode::FORBIDDEN, Json(json!({})))),
     };
-    return res;
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::header::LINK;
+
+    use super::Page;
+
+    fn links(page: &Page) -> (String, Option<String>) {
+        let headers = page.headers();
+        (
+            headers["x-total-count"].to_str().unwrap().to_string(),
+            headers
+                .get(LINK)
+                .map(|link| link.to_str().unwrap().to_string()),
+        )
+    }
+
+    #[test]
+    fn test_pagination_headers() {
+        let page = |query, total, limit, offset| Page {
+            path: "users",
+            query,
+            total,
+            limit,
+            offset,
+        };
+
+        // the rest of the query is kept, limit and offset are rewritten
+        assert_eq!(
+            links(&page(Some("limit=10&name=Ann&offset=20"), 45, 10, 20)),
+            (
+                "45".to_string(),
+                Some(
+                    [
+                        "</users?name=Ann&limit=10&offset=0>; rel=\"first\"",
+                        "</users?name=Ann&limit=10&offset=10>; rel=\"prev\"",
+                        "</users?name=Ann&limit=10&offset=30>; rel=\"next\"",
+                        "</users?name=Ann&limit=10&offset=40>; rel=\"last\"",
+                    ]
+                    .join(", ")
+                )
+            )
+        );
+        // no prev on the first page, no next on the last one
+        assert_eq!(
+            links(&page(None, 40, 20, 0)).1.unwrap(),
+            "</users?limit=20&offset=0>; rel=\"first\", \
+             </users?limit=20&offset=20>; rel=\"next\", \
+             </users?limit=20&offset=20>; rel=\"last\""
+        );
+        // past the end, prev points at the last page
+        assert_eq!(
+            links(&page(None, 45, 10, 100)).1.unwrap(),
+            "</users?limit=10&offset=0>; rel=\"first\", \
+             </users?limit=10&offset=40>; rel=\"prev\", \
+             </users?limit=10&offset=40>; rel=\"last\""
+        );
+        assert_eq!(
+            links(&page(None, 0, 10, 0)).1.unwrap(),
+            "</users?limit=10&offset=0>; rel=\"first\", \
+             </users?limit=10&offset=0>; rel=\"last\""
+        );
+        assert_eq!(
+            links(&page(Some("limit=0"), 7, 0, 0)),
+            ("7".to_string(), None)
+        );
+    }
 }
@@ -0,0 +1,54 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::helpers::json::{read_json, write_table_file};
+
+use super::Storage;
+
+// ! aginisi/{table}.json, one pretty printed array per table
+pub struct JsonStorage {
+    dir: PathBuf,
+}
+
+impl JsonStorage {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl Storage for JsonStorage {
+    fn list(&self) -> Vec<String> {
+        let mut names = Vec::<String>::new();
+        if let Ok(entries) = fs::read_dir(&self.dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_file()
+                    && let Some(name) = path
+                        .file_name()
+                        .and_then(|t| t.to_str())
+                        .and_then(|t| t.strip_suffix(".json"))
+                    && !name.starts_with('.')
+                {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names
+    }
+
+    fn load(&self, table: &str) -> Vec<Value> {
+        match read_json(&self.dir, table) {
+            Value::Array(rows) => rows,
+            _ => vec![],
+        }
+    }
+
+    fn save(&self, table: &str, rows: &[Value]) {
+        let json = serde_json::to_string_pretty(rows).unwrap();
+        write_table_file(&self.dir, table, &json);
+    }
+}
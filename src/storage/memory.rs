@@ -0,0 +1,35 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use serde_json::Value;
+
+use super::Storage;
+
+// ! tables kept in memory, for tests
+// clones share their tables, so a test can keep one to look at what was saved
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStorage(Arc<Mutex<HashMap<String, Vec<Value>>>>);
+
+impl MemoryStorage {
+    pub fn new(tables: HashMap<String, Vec<Value>>) -> Self {
+        Self(Arc::new(Mutex::new(tables)))
+    }
+
+    fn tables(&self) -> MutexGuard<'_, HashMap<String, Vec<Value>>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn list(&self) -> Vec<String> {
+        self.tables().keys().cloned().collect()
+    }
+
+    fn load(&self, table: &str) -> Vec<Value> {
+        self.tables().get(table).cloned().unwrap_or_default()
+    }
+
+    fn save(&self, table: &str, rows: &[Value]) {
+        self.tables().insert(table.to_string(), rows.to_vec());
+    }
+}
@@ -0,0 +1,84 @@
+pub mod json;
+#[cfg(test)]
+pub mod memory;
+pub mod ndjson;
+pub mod sqlite;
+
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::consts::FOLDER_NAME;
+use crate::model::toml_config::{Config, StorageType};
+
+use self::json::JsonStorage;
+use self::ndjson::NdjsonStorage;
+use self::sqlite::SqliteStorage;
+
+// ! where tables live on disk
+// the table store keeps every table in memory and only talks to a storage
+// backend to load a table the first time and to persist it when it changed
+
+pub trait Storage: Send + Sync {
+    fn list(&self) -> Vec<String>;
+    fn load(&self, table: &str) -> Vec<Value>;
+    fn save(&self, table: &str, rows: &[Value]);
+
+    /// Persists a table whose only change since the last save is `added` new
+    /// rows at the end of `rows`. Backends that cannot append rewrite the table.
+    fn append(&self, table: &str, rows: &[Value], added: usize) {
+        let _ = added;
+        self.save(table, rows)
+    }
+}
+
+pub fn storage_from_config(config: &Config) -> Box<dyn Storage> {
+    match config.storage.clone().unwrap_or_default() {
+        StorageType::Json => Box::new(JsonStorage::new(FOLDER_NAME)),
+        StorageType::Ndjson => Box::new(NdjsonStorage::new(FOLDER_NAME)),
+        StorageType::Sqlite => Box::new(SqliteStorage::open(
+            Path::new(FOLDER_NAME).join("aginisi.db"),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use serde_json::json;
+
+    use super::Storage;
+    use super::json::JsonStorage;
+    use super::ndjson::NdjsonStorage;
+    use super::sqlite::SqliteStorage;
+
+    fn round_trip(storage: &dyn Storage) {
+        let mut rows = vec![json!({ "id": 1, "name": "Alice" })];
+        storage.save("users", &rows);
+        rows.push(json!({ "id": 2, "name": "Bob\nSmith" }));
+        rows.push(json!({ "id": 3, "tags": ["a", "b"] }));
+        storage.append("users", &rows, 2);
+
+        assert_eq!(storage.load("users"), rows);
+        assert_eq!(storage.list(), vec!["users".to_string()]);
+
+        rows.remove(0);
+        storage.save("users", &rows);
+        assert_eq!(storage.load("users"), rows);
+        assert!(storage.load("missing").is_empty());
+    }
+
+    #[test]
+    fn test_json_ndjson_and_sqlite_round_trip() {
+        let dir = std::env::temp_dir().join(format!("aginisi_storage_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        round_trip(&JsonStorage::new(&dir));
+        assert!(!dir.join(".users.json.tmp").exists());
+        round_trip(&NdjsonStorage::new(&dir));
+        round_trip(&SqliteStorage::open(dir.join("aginisi.db")));
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,85 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use super::Storage;
+
+// ! aginisi/{table}.ndjson, one record per line
+// new records are appended to the end of the file instead of rewriting it
+pub struct NdjsonStorage {
+    dir: PathBuf,
+}
+
+impl NdjsonStorage {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    fn path(&self, table: &str) -> PathBuf {
+        self.dir.join(format!("{}.ndjson", table))
+    }
+}
+
+fn to_lines(rows: &[Value]) -> String {
+    let mut lines = String::new();
+    for row in rows {
+        // serde_json escapes newlines inside strings, so a record is always one line
+        lines.push_str(&serde_json::to_string(row).unwrap());
+        lines.push('\n');
+    }
+    lines
+}
+
+impl Storage for NdjsonStorage {
+    fn list(&self) -> Vec<String> {
+        let mut names = Vec::<String>::new();
+        if let Ok(entries) = fs::read_dir(&self.dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_file()
+                    && let Some(name) = path
+                        .file_name()
+                        .and_then(|t| t.to_str())
+                        .and_then(|t| t.strip_suffix(".ndjson"))
+                    && !name.starts_with('.')
+                {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names.sort();
+        names
+    }
+
+    fn load(&self, table: &str) -> Vec<Value> {
+        let content = fs::read_to_string(self.path(table)).unwrap_or_default();
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()
+    }
+
+    fn save(&self, table: &str, rows: &[Value]) {
+        let tmp_path = self.dir.join(format!(".{}.ndjson.tmp", table));
+        let mut file = File::create(&tmp_path).unwrap();
+        file.write_all(to_lines(rows).as_bytes()).unwrap();
+        file.sync_all().unwrap();
+        fs::rename(&tmp_path, self.path(table)).unwrap();
+    }
+
+    fn append(&self, table: &str, rows: &[Value], added: usize) {
+        let new_rows = &rows[rows.len().saturating_sub(added)..];
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path(table))
+            .unwrap();
+        file.write_all(to_lines(new_rows).as_bytes()).unwrap();
+        file.sync_all().unwrap();
+    }
+}
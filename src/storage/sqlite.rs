@@ -0,0 +1,90 @@
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+
+use rusqlite::{Connection, params};
+use serde_json::Value;
+
+use super::Storage;
+
+// ! aginisi/aginisi.db, every record stored as a json document
+// `records` keeps the rows of all tables in order, `tables` remembers tables
+// that exist but are empty
+pub struct SqliteStorage {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    pub fn open(path: impl AsRef<Path>) -> Self {
+        let conn = Connection::open(path).unwrap();
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS tables (name TEXT PRIMARY KEY);
+             CREATE TABLE IF NOT EXISTS records (
+                 tbl TEXT NOT NULL,
+                 pos INTEGER NOT NULL,
+                 doc TEXT NOT NULL,
+                 PRIMARY KEY (tbl, pos)
+             );",
+        )
+        .unwrap();
+        Self {
+            conn: Mutex::new(conn),
+        }
+    }
+
+    fn insert_rows(conn: &Connection, table: &str, rows: &[Value], from: usize) {
+        let mut stmt = conn
+            .prepare_cached("INSERT INTO records (tbl, pos, doc) VALUES (?1, ?2, ?3)")
+            .unwrap();
+        for (i, row) in rows.iter().enumerate() {
+            stmt.execute(params![table, (from + i) as i64, row.to_string()])
+                .unwrap();
+        }
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn list(&self) -> Vec<String> {
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut stmt = conn
+            .prepare("SELECT name FROM tables ORDER BY name")
+            .unwrap();
+        stmt.query_map([], |row| row.get(0))
+            .unwrap()
+            .filter_map(Result::ok)
+            .collect()
+    }
+
+    fn load(&self, table: &str) -> Vec<Value> {
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut stmt = conn
+            .prepare("SELECT doc FROM records WHERE tbl = ?1 ORDER BY pos")
+            .unwrap();
+        stmt.query_map([table], |row| row.get::<_, String>(0))
+            .unwrap()
+            .filter_map(Result::ok)
+            .filter_map(|doc| serde_json::from_str(&doc).ok())
+            .collect()
+    }
+
+    fn save(&self, table: &str, rows: &[Value]) {
+        let mut conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        let tx = conn.transaction().unwrap();
+        tx.execute("INSERT OR IGNORE INTO tables (name) VALUES (?1)", [table])
+            .unwrap();
+        tx.execute("DELETE FROM records WHERE tbl = ?1", [table])
+            .unwrap();
+        Self::insert_rows(&tx, table, rows, 0);
+        tx.commit().unwrap();
+    }
+
+    fn append(&self, table: &str, rows: &[Value], added: usize) {
+        let from = rows.len().saturating_sub(added);
+        let mut conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        let tx = conn.transaction().unwrap();
+        tx.execute("INSERT OR IGNORE INTO tables (name) VALUES (?1)", [table])
+            .unwrap();
+        Self::insert_rows(&tx, table, &rows[from..], from);
+        tx.commit().unwrap();
+    }
+}
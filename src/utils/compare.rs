@@ -0,0 +1,137 @@
+use std::cmp::Ordering;
+
+use chrono::{DateTime, Months, NaiveDate, NaiveDateTime, TimeDelta, Utc};
+use serde_json::Value;
+
+// ! type-aware ordering of json values
+// numbers compare numerically, strings that both parse as ISO dates compare
+// chronologically, other strings lexicographically and dates come before other
+// strings, so the order stays total on mixed columns. values of different types
+// are ordered by type: null < bool < number < string < array < object
+
+pub fn compare_values(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => {
+            let (x, y) = (x.as_f64().unwrap_or(0.0), y.as_f64().unwrap_or(0.0));
+            x.partial_cmp(&y).unwrap_or(Ordering::Equal)
+        }
+        (Value::String(x), Value::String(y)) => match (parse_date(x), parse_date(y)) {
+            (Some(dx), Some(dy)) => dx.cmp(&dy).then_with(|| x.cmp(y)),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => x.cmp(y),
+        },
+        (Value::Bool(x), Value::Bool(y)) => x.cmp(y),
+        (Value::Array(x), Value::Array(y)) => {
+            for (x, y) in x.iter().zip(y.iter()) {
+                let ord = compare_values(x, y);
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+            x.len().cmp(&y.len())
+        }
+        _ => type_rank(a).cmp(&type_rank(b)),
+    }
+}
+
+// ! filter operands
+// a filter only compares a field with an operand of the same kind: numbers with
+// numbers, strings with strings (as dates when both parse) and booleans with
+// booleans. anything else, null included, is not comparable and the operator
+// does not match. `now`, `now-7d` or `now+1h` stand for a moment relative to
+// the time of the request
+
+pub fn compare_operand(field: &Value, operand: &Value) -> Option<Ordering> {
+    match (field, operand) {
+        (Value::Number(x), Value::Number(y)) => x.as_f64()?.partial_cmp(&y.as_f64()?),
+        (Value::String(x), Value::String(y)) => {
+            if let Some(y) = relative_date(y) {
+                return parse_date(x).map(|x| x.cmp(&y));
+            }
+            match (parse_date(x), parse_date(y)) {
+                (Some(x), Some(y)) => Some(x.cmp(&y)),
+                _ => Some(x.cmp(y)),
+            }
+        }
+        (Value::Bool(x), Value::Bool(y)) => Some(x.cmp(y)),
+        _ => None,
+    }
+}
+
+// json equality, except that `1` and `1.0` are the same number
+pub fn values_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x.as_f64() == y.as_f64(),
+        _ => a == b,
+    }
+}
+
+/// `now` optionally followed by `+` or `-`, an amount and a unit: `s`econds,
+/// `m`inutes, `h`ours, `d`ays, `w`eeks, `M`onths or `y`ears.
+pub fn relative_date(value: &str) -> Option<DateTime<Utc>> {
+    let now = Utc::now();
+    let rest = value.trim().strip_prefix("now")?;
+    if rest.is_empty() {
+        return Some(now);
+    }
+
+    let (sign, rest) = match rest.split_at_checked(1)? {
+        ("+", rest) => (1, rest),
+        ("-", rest) => (-1, rest),
+        _ => return None,
+    };
+    let unit = rest.chars().last()?;
+    let amount: i64 = rest[..rest.len() - unit.len_utf8()].parse().ok()?;
+
+    let delta = match unit {
+        's' => TimeDelta::try_seconds(amount)?,
+        'm' => TimeDelta::try_minutes(amount)?,
+        'h' => TimeDelta::try_hours(amount)?,
+        'd' => TimeDelta::try_days(amount)?,
+        'w' => TimeDelta::try_weeks(amount)?,
+        'M' | 'y' => {
+            let months =
+                Months::new(u32::try_from(amount).ok()? * if unit == 'y' { 12 } else { 1 });
+            return if sign > 0 {
+                now.checked_add_months(months)
+            } else {
+                now.checked_sub_months(months)
+            };
+        }
+        _ => return None,
+    };
+    if sign > 0 {
+        now.checked_add_signed(delta)
+    } else {
+        now.checked_sub_signed(delta)
+    }
+}
+
+fn type_rank(value: &Value) -> u8 {
+    match value {
+        Value::Null => 0,
+        Value::Bool(_) => 1,
+        Value::Number(_) => 2,
+        Value::String(_) => 3,
+        Value::Array(_) => 4,
+        Value::Object(_) => 5,
+    }
+}
+
+/// Parses RFC 3339 timestamps, naive `YYYY-MM-DDTHH:MM:SS` date-times and plain
+/// `YYYY-MM-DD` dates. Naive values are taken to be UTC.
+pub fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"] {
+        if let Ok(date) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(date.and_utc());
+        }
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|date| date.and_utc())
+}
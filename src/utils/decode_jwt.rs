@@ -3,6 +3,31 @@ use jsonwebtoken::{Validation, decode};
 use crate::{consts::KEYS, model::auth::Claims};
 
 pub fn decode_jwt(token: &str) -> bool {
-    let token = token.strip_prefix("Bearer ").unwrap();
-    decode::<Claims>(&token, &KEYS.decoding, &Validation::default()).is_ok()
+    let Some(token) = token.strip_prefix("Bearer ") else {
+        return false;
+    };
+    decode::<Claims>(token, &KEYS.decoding, &Validation::default()).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use jsonwebtoken::{Header, encode};
+    use serde_json::json;
+
+    use super::decode_jwt;
+    use crate::consts::KEYS;
+
+    #[test]
+    fn test_string_and_numeric_subjects() {
+        let now = Utc::now().timestamp();
+        for sub in [json!("01J0Z5"), json!(7)] {
+            let claims = json!({
+                "sub": sub, "exp": now + 60, "iat": now, "nbf": now, "iss": "aginisi.com"
+            });
+            let token = encode(&Header::default(), &claims, &KEYS.encoding).unwrap();
+            assert!(decode_jwt(&format!("Bearer {}", token)));
+        }
+        assert!(!decode_jwt("Bearer nope"));
+    }
 }
@@ -0,0 +1,95 @@
+use serde_json::Value;
+
+// ! field paths
+// `name`, dotted `address.city`, indexed `tags[0]` or `tags.0`, wildcard
+// `items[*].sku` and JSON Pointer `/tags/0`. a wildcard resolves to every
+// element, so a path can point at several values
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Key(String),
+    Index(usize),
+    Wildcard,
+}
+
+fn parse_pointer(path: &str) -> Vec<Segment> {
+    path.split('/')
+        .skip(1)
+        .map(|token| match token {
+            "*" => Segment::Wildcard,
+            _ => Segment::Key(token.replace("~1", "/").replace("~0", "~")),
+        })
+        .collect()
+}
+
+fn parse_dotted(path: &str) -> Vec<Segment> {
+    let mut segments = vec![];
+    for part in path.split('.') {
+        let (name, mut rest) = match part.find('[') {
+            Some(i) => (&part[..i], &part[i..]),
+            None => (part, ""),
+        };
+        match name {
+            "" => {}
+            "*" => segments.push(Segment::Wildcard),
+            _ => segments.push(Segment::Key(name.to_string())),
+        }
+        while let Some(end) = rest.find(']') {
+            let index = rest[1..end].trim();
+            segments.push(match index.parse::<usize>() {
+                Ok(i) => Segment::Index(i),
+                Err(_) if index == "*" => Segment::Wildcard,
+                Err(_) => Segment::Key(index.trim_matches(['\'', '"']).to_string()),
+            });
+            rest = &rest[end + 1..];
+        }
+    }
+    segments
+}
+
+fn step<'a>(value: &'a Value, segment: &Segment, out: &mut Vec<&'a Value>) {
+    match (segment, value) {
+        (Segment::Key(key), Value::Object(obj)) => out.extend(obj.get(key)),
+        // numeric keys index into arrays, so `tags.0` and `/tags/0` work
+        (Segment::Key(key), Value::Array(arr)) => {
+            if let Ok(i) = key.parse::<usize>() {
+                out.extend(arr.get(i));
+            }
+        }
+        (Segment::Index(i), Value::Array(arr)) => out.extend(arr.get(*i)),
+        (Segment::Index(i), Value::Object(obj)) => out.extend(obj.get(&i.to_string())),
+        (Segment::Wildcard, Value::Array(arr)) => out.extend(arr.iter()),
+        (Segment::Wildcard, Value::Object(obj)) => out.extend(obj.values()),
+        _ => {}
+    }
+}
+
+/// The steps of `path` inside `row`. A top-level key that matches the whole
+/// path wins, so fields with dots in their name stay reachable.
+pub fn segments(row: &Value, path: &str) -> Vec<Segment> {
+    if row.get(path).is_some() {
+        vec![Segment::Key(path.to_string())]
+    } else if path.starts_with('/') {
+        parse_pointer(path)
+    } else {
+        parse_dotted(path)
+    }
+}
+
+/// Every value `path` points at inside `row`.
+pub fn resolve<'a>(row: &'a Value, path: &str) -> Vec<&'a Value> {
+    let mut current = vec![row];
+    for segment in &segments(row, path) {
+        let mut next = vec![];
+        for value in current {
+            step(value, segment, &mut next);
+        }
+        current = next;
+    }
+    current
+}
+
+/// The first value `path` points at, for places that need a single value.
+pub fn resolve_first<'a>(row: &'a Value, path: &str) -> Option<&'a Value> {
+    resolve(row, path).into_iter().next()
+}
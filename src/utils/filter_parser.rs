@@ -0,0 +1,564 @@
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::model::filter_type::FilterType;
+use crate::utils::compare::relative_date;
+
+// ! where expressions
+// age >= 21 and (name like 'A%' or status in ['x', 'y']) and not deleted
+//
+// `or` binds looser than `and`, `not` binds tightest. a condition is a field
+// path followed by an operator and a value; a bare path means `path = true`.
+// keywords are case-insensitive and a field named like one can be quoted with
+// backticks
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// 1-based character column the error points at.
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at column {}", self.message, self.column)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Field(String),
+    Value(Value),
+    Op(&'static str),
+    Open(char),
+    Close(char),
+    Comma,
+    End,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(s) => write!(f, "'{}'", s),
+            Token::Field(s) => write!(f, "`{}`", s),
+            Token::Value(v) => write!(f, "{}", v),
+            Token::Op(op) => write!(f, "'{}'", op),
+            Token::Open(c) | Token::Close(c) => write!(f, "'{}'", c),
+            Token::Comma => write!(f, "','"),
+            Token::End => write!(f, "end of input"),
+        }
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "and",
+    "or",
+    "not",
+    "like",
+    "ilike",
+    "in",
+    "between",
+    "is",
+    "exists",
+    "contains",
+    "startswith",
+    "endswith",
+    "matches",
+];
+
+const OPERATORS: &[&str] = &["==", "!=", "<>", ">=", "<=", "=", ">", "<", "~"];
+
+// how deep groups, `not`s and lists may nest, the parser recurses once per level
+const MAX_DEPTH: usize = 64;
+
+fn is_keyword(word: &str) -> bool {
+    KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(word))
+}
+
+// ! lexer
+// every token remembers the column it starts at
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+    let error = |column: usize, message: &str| ParseError {
+        column: column + 1,
+        message: message.to_string(),
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let token = match c {
+            '(' | '[' => {
+                i += 1;
+                Token::Open(c)
+            }
+            ')' | ']' => {
+                i += 1;
+                Token::Close(c)
+            }
+            ',' => {
+                i += 1;
+                Token::Comma
+            }
+            '\'' | '"' | '`' => {
+                let mut text = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => return Err(error(start, "Unterminated quote")),
+                        Some('\\') if i + 1 < chars.len() => {
+                            text.push(chars[i + 1]);
+                            i += 2;
+                        }
+                        Some(&q) if q == c => {
+                            i += 1;
+                            break;
+                        }
+                        Some(&other) => {
+                            text.push(other);
+                            i += 1;
+                        }
+                    }
+                }
+                match c {
+                    '`' => Token::Field(text),
+                    _ => Token::Value(Value::String(text)),
+                }
+            }
+            c if c.is_ascii_digit()
+                || (c == '-' && chars.get(i + 1).is_some_and(char::is_ascii_digit)) =>
+            {
+                i += 1;
+                while i < chars.len()
+                    && (chars[i].is_ascii_alphanumeric() || matches!(chars[i], '.' | '+' | '-'))
+                {
+                    // stop at a sign that is not an exponent sign
+                    if matches!(chars[i], '+' | '-') && !matches!(chars[i - 1], 'e' | 'E') {
+                        break;
+                    }
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                match serde_json::from_str::<Value>(&text) {
+                    Ok(number @ Value::Number(_)) => Token::Value(number),
+                    _ => return Err(error(start, &format!("Invalid number '{}'", text))),
+                }
+            }
+            c if c.is_alphabetic() || matches!(c, '_' | '$' | '/') => {
+                i += 1;
+                while i < chars.len() {
+                    match chars[i] {
+                        c if c.is_alphanumeric() || matches!(c, '_' | '$' | '/' | '.' | '-') => {
+                            i += 1
+                        }
+                        // `items[*]` and `tags[0]` belong to the path, `in [` does not
+                        '[' => {
+                            let end = chars[i..].iter().position(|&c| c == ']');
+                            match end {
+                                Some(end)
+                                    if end > 1
+                                        && chars[i + 1..i + end]
+                                            .iter()
+                                            .all(|c| c.is_ascii_digit() || *c == '*') =>
+                                {
+                                    i += end + 1
+                                }
+                                _ => break,
+                            }
+                        }
+                        _ => break,
+                    }
+                }
+                let word: String = chars[start..i].iter().collect();
+                match word.to_ascii_lowercase().as_str() {
+                    "true" => Token::Value(Value::Bool(true)),
+                    "false" => Token::Value(Value::Bool(false)),
+                    "null" => Token::Value(Value::Null),
+                    _ => Token::Ident(word),
+                }
+            }
+            _ => {
+                let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+                let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) else {
+                    return Err(error(start, &format!("Unexpected character '{}'", c)));
+                };
+                i += op.len();
+                Token::Op(op)
+            }
+        };
+        tokens.push((start + 1, token));
+    }
+    tokens.push((chars.len() + 1, Token::End));
+    Ok(tokens)
+}
+
+// ! parser
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].1
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.pos].1.clone();
+        if self.pos + 1 < self.tokens.len() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn error(&self, expected: &str) -> ParseError {
+        ParseError {
+            column: self.tokens[self.pos].0,
+            message: format!("Expected {}, found {}", expected, self.peek()),
+        }
+    }
+
+    // runs `parse` one level deeper, failing instead of recursing past MAX_DEPTH
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<T, ParseError> {
+        if self.depth == MAX_DEPTH {
+            return Err(ParseError {
+                column: self.tokens[self.pos].0,
+                message: format!("Nested deeper than {} levels", MAX_DEPTH),
+            });
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Token::Ident(word) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.is_keyword(keyword);
+        if found {
+            self.next();
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), ParseError> {
+        match self.eat_keyword(keyword) {
+            true => Ok(()),
+            false => Err(self.error(&format!("'{}'", keyword))),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<FilterType, ParseError> {
+        let mut filters = vec![self.parse_and()?];
+        while self.eat_keyword("or") {
+            filters.push(self.parse_and()?);
+        }
+        Ok(match filters.len() {
+            1 => filters.remove(0),
+            _ => FilterType::Any { filters },
+        })
+    }
+
+    fn parse_and(&mut self) -> Result<FilterType, ParseError> {
+        let mut filters = vec![self.parse_unary()?];
+        while self.eat_keyword("and") {
+            filters.push(self.parse_unary()?);
+        }
+        Ok(match filters.len() {
+            1 => filters.remove(0),
+            _ => FilterType::All { filters },
+        })
+    }
+
+    fn parse_unary(&mut self) -> Result<FilterType, ParseError> {
+        if self.eat_keyword("not") {
+            return Ok(FilterType::Not {
+                inner: Box::new(self.nested(Self::parse_unary)?),
+            });
+        }
+        match self.peek().clone() {
+            Token::Open('(') => {
+                self.next();
+                let inner = self.nested(Self::parse_or)?;
+                if *self.peek() != Token::Close(')') {
+                    return Err(self.error("')'"));
+                }
+                self.next();
+                Ok(inner)
+            }
+            Token::Ident(word) if !is_keyword(&word) => {
+                self.next();
+                self.parse_condition(Value::String(word))
+            }
+            Token::Field(field) => {
+                self.next();
+                self.parse_condition(Value::String(field))
+            }
+            _ => Err(self.error("a field or '('")),
+        }
+    }
+
+    fn parse_condition(&mut self, key: Value) -> Result<FilterType, ParseError> {
+        use FilterType::*;
+
+        if let Token::Op(op) = self.peek().clone() {
+            self.next();
+            let value = self.parse_value()?;
+            return Ok(match op {
+                "=" | "==" => Equals { key, value },
+                "!=" | "<>" => NotEquals { key, value },
+                ">" => GreaterThan { key, value },
+                ">=" => GreaterThanOrEqualsTo { key, value },
+                "<" => LessThanThan { key, value },
+                "<=" => LessThanThanOrEqualsTo { key, value },
+                _ => Regex {
+                    key,
+                    pattern: value.into(),
+                },
+            });
+        }
+
+        let negate = |filter: FilterType| Not {
+            inner: Box::new(filter),
+        };
+        let Token::Ident(word) = self.peek().clone() else {
+            return Ok(Equals {
+                key,
+                value: Value::Bool(true),
+            });
+        };
+        let word = word.to_ascii_lowercase();
+        match word.as_str() {
+            "not" => {
+                self.next();
+                let Token::Ident(word) = self.peek().clone() else {
+                    return Err(self.error("'like', 'in' or 'between'"));
+                };
+                match word.to_ascii_lowercase().as_str() {
+                    "like" => {
+                        self.next();
+                        Ok(NotLike {
+                            key,
+                            pattern: self.parse_string()?.into(),
+                        })
+                    }
+                    "in" => {
+                        self.next();
+                        Ok(NotInSet {
+                            key,
+                            value: self.parse_list()?,
+                        })
+                    }
+                    "between" => Ok(negate(self.parse_condition(key)?)),
+                    _ => Err(self.error("'like', 'in' or 'between'")),
+                }
+            }
+            "like" | "ilike" | "startswith" | "endswith" | "matches" => {
+                self.next();
+                let pattern = self.parse_string()?;
+                Ok(match word.as_str() {
+                    "like" => Like {
+                        key,
+                        pattern: pattern.into(),
+                    },
+                    "ilike" => ILike {
+                        key,
+                        pattern: pattern.into(),
+                    },
+                    "startswith" => StartsWith {
+                        key,
+                        value: pattern,
+                    },
+                    "endswith" => EndsWith {
+                        key,
+                        value: pattern,
+                    },
+                    _ => Regex {
+                        key,
+                        pattern: pattern.into(),
+                    },
+                })
+            }
+            "in" => {
+                self.next();
+                Ok(InSet {
+                    key,
+                    value: self.parse_list()?,
+                })
+            }
+            "between" => {
+                self.next();
+                let from = self.parse_value()?;
+                self.expect_keyword("and")?;
+                let to = self.parse_value()?;
+                Ok(Between { key, from, to })
+            }
+            "is" => {
+                self.next();
+                let not = self.eat_keyword("not");
+                if *self.peek() != Token::Value(Value::Null) {
+                    return Err(self.error("'null'"));
+                }
+                self.next();
+                Ok(match not {
+                    true => negate(IsNull { key }),
+                    false => IsNull { key },
+                })
+            }
+            "exists" => {
+                self.next();
+                Ok(Exists { key })
+            }
+            "contains" => {
+                self.next();
+                Ok(ArrayContains {
+                    key,
+                    value: self.parse_value()?,
+                })
+            }
+            "and" | "or" => Ok(Equals {
+                key,
+                value: Value::Bool(true),
+            }),
+            _ => Err(self.error("an operator")),
+        }
+    }
+
+    // a literal, or an unquoted relative date such as `now-7d`
+    fn parse_value(&mut self) -> Result<Value, ParseError> {
+        match self.peek().clone() {
+            Token::Value(value) => {
+                self.next();
+                Ok(value)
+            }
+            Token::Ident(word) if relative_date(&word).is_some() => {
+                self.next();
+                Ok(Value::String(word))
+            }
+            Token::Open('[') => self.nested(Self::parse_list),
+            _ => Err(self.error("a value")),
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<Value, ParseError> {
+        match self.peek() {
+            Token::Value(Value::String(_)) => self.parse_value(),
+            _ => Err(self.error("a quoted string")),
+        }
+    }
+
+    // `['a', 'b']` or `('a', 'b')`
+    fn parse_list(&mut self) -> Result<Value, ParseError> {
+        let close = match self.peek() {
+            Token::Open('[') => ']',
+            Token::Open('(') => ')',
+            _ => return Err(self.error("a list")),
+        };
+        self.next();
+
+        let mut values = vec![];
+        if *self.peek() == Token::Close(close) {
+            self.next();
+            return Ok(Value::Array(values));
+        }
+        loop {
+            values.push(self.parse_value()?);
+            match self.peek() {
+                Token::Comma => self.next(),
+                Token::Close(c) if *c == close => {
+                    self.next();
+                    return Ok(Value::Array(values));
+                }
+                _ => return Err(self.error(&format!("',' or '{}'", close))),
+            };
+        }
+    }
+}
+
+/// Compiles a `where` expression into a filter.
+pub fn parse_where(input: &str) -> Result<FilterType, ParseError> {
+    let mut parser = Parser {
+        tokens: tokenize(input)?,
+        pos: 0,
+        depth: 0,
+    };
+    let filter = parser.parse_or()?;
+    match parser.peek() {
+        Token::End => Ok(filter),
+        _ => Err(parser.error("'and', 'or' or end of input")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::parse_where;
+
+    #[test]
+    fn test_parse_and_evaluate() {
+        let data = [
+            json!({ "id": 1, "name": "Alice", "age": 31, "status": "z", "deleted": false }),
+            json!({ "id": 2, "name": "Bob", "age": 25, "status": "x" }),
+            json!({ "id": 3, "name": "Anna", "age": 40, "status": "y", "deleted": true }),
+            json!({ "id": 4, "name": "Alex", "age": 18, "tags": ["a"], "address": { "city": "Lagos" } }),
+        ];
+        let ids = |input: &str| -> Vec<i64> {
+            let filter = parse_where(input).unwrap();
+            data.iter()
+                .filter(|row| filter.evaluate(row))
+                .map(|row| row["id"].as_i64().unwrap())
+                .collect()
+        };
+
+        assert_eq!(
+            ids("age >= 21 and (name like 'A%' or status in ['x', 'y']) and not deleted"),
+            [1, 2]
+        );
+        assert_eq!(
+            ids("age between 20 and 35 OR address.city = \"Lagos\""),
+            [1, 2, 4]
+        );
+        assert_eq!(ids("deleted is not null and not (status = 'z')"), [3]);
+        assert_eq!(
+            ids("tags contains 'a' or name not in ('Alice', 'Bob', 'Alex')"),
+            [3, 4]
+        );
+        assert_eq!(ids("age > -1 and age <= 1e2 and name != 'Bob'"), [1, 3, 4]);
+    }
+
+    #[test]
+    fn test_error_positions() {
+        let column = |input: &str| parse_where(input).unwrap_err().column;
+
+        assert_eq!(column("age >= "), 8);
+        assert_eq!(column("age >= 21 and (name = 'x'"), 26);
+        assert_eq!(column("age ? 3"), 5);
+        assert_eq!(column("name = 'open"), 8);
+        assert_eq!(column("age 21"), 5);
+        assert_eq!(
+            parse_where("age >= and").unwrap_err().to_string(),
+            "Expected a value, found 'and' at column 8"
+        );
+        assert_eq!(column(&format!("{}a = 1", "(".repeat(50_000))), 66);
+        assert_eq!(column(&format!("{}a", "not ".repeat(50_000))), 261);
+        assert_eq!(column(&format!("a in [{}", "[".repeat(50_000))), 71);
+        assert!(parse_where(&format!("{}a = 1{}", "(".repeat(64), ")".repeat(64))).is_ok());
+    }
+}
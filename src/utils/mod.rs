@@ -1 +1,5 @@
+pub mod compare;
 pub mod decode_jwt;
+pub mod field_path;
+pub mod filter_parser;
+pub mod search;
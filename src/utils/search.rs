@@ -0,0 +1,288 @@
+use std::iter;
+
+use serde_json::{Map, Value};
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+use super::field_path::resolve;
+
+// ! ?q=alise lagos
+// the query and the searched fields are split into words, lowercased and
+// stripped of accents. a record matches when every query word matches a word of
+// one of its fields: exactly, as a prefix (search as you type) or within a few
+// typos. better matches rank higher
+
+const EXACT: f64 = 1.0;
+const PREFIX: f64 = 0.8;
+const FUZZY: f64 = 0.6;
+const TYPO_PENALTY: f64 = 0.15;
+const PHRASE_BONUS: f64 = 0.5;
+
+struct Word {
+    start: usize,
+    end: usize,
+    text: Vec<char>,
+}
+
+/// Lowercases `text` and strips its accents: `Éclair` becomes `eclair`.
+pub fn fold(text: &str) -> String {
+    text.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+// runs of letters and digits, with their byte range in `text`
+fn words(text: &str) -> Vec<Word> {
+    let mut words = vec![];
+    let mut start = None;
+    for (i, c) in text.char_indices().chain(iter::once((text.len(), ' '))) {
+        if c.is_alphanumeric() {
+            start.get_or_insert(i);
+        } else if let Some(start) = start.take() {
+            words.push(Word {
+                start,
+                end: i,
+                text: fold(&text[start..i]).chars().collect(),
+            });
+        }
+    }
+    words
+}
+
+// edit distance where swapping two neighbouring letters counts as one typo
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    d[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+// short words must be spelled right, longer ones may carry a typo or two
+fn max_typos(len: usize) -> usize {
+    match len {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+fn term_score(term: &[char], word: &[char]) -> f64 {
+    if term == word {
+        return EXACT;
+    }
+    if word.starts_with(term) {
+        return PREFIX;
+    }
+    let typos = edit_distance(term, word);
+    if typos <= max_typos(term.len()) {
+        return FUZZY - TYPO_PENALTY * typos as f64;
+    }
+    // a typo in a word that is still being typed
+    if word.len() > term.len() {
+        let typos = edit_distance(term, &word[..term.len()]);
+        if typos <= max_typos(term.len()) {
+            return FUZZY - TYPO_PENALTY * (typos + 1) as f64;
+        }
+    }
+    0.0
+}
+
+pub struct Search {
+    phrase: String,
+    terms: Vec<Vec<char>>,
+}
+
+impl Search {
+    pub fn new(query: &str) -> Search {
+        let terms: Vec<Vec<char>> = words(query).into_iter().map(|word| word.text).collect();
+        Search {
+            phrase: fold(query.trim()),
+            terms,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// The relevance of `row`, or `None` when it does not match. `fields` are
+    /// the paths to search, every string in the record when not given.
+    pub fn score(&self, row: &Value, fields: Option<&[String]>) -> Option<f64> {
+        let texts = texts(row, fields);
+        let words: Vec<Vec<char>> = texts
+            .iter()
+            .flat_map(|(_, text)| words(text))
+            .map(|word| word.text)
+            .collect();
+
+        let mut total = 0.0;
+        for term in &self.terms {
+            let best = words
+                .iter()
+                .map(|word| term_score(term, word))
+                .fold(0.0, f64::max);
+            if best == 0.0 {
+                return None;
+            }
+            total += best;
+        }
+        let mut score = total / self.terms.len() as f64;
+        if self.terms.len() > 1
+            && texts
+                .iter()
+                .any(|(_, text)| fold(text).contains(&self.phrase))
+        {
+            score += PHRASE_BONUS;
+        }
+        Some(score)
+    }
+
+    /// The searched fields that matched, HTML escaped with the matching words
+    /// wrapped in `<mark>` tags, keyed by their path.
+    pub fn highlight(&self, row: &Value, fields: Option<&[String]>) -> Value {
+        let mut highlights = Map::new();
+        for (path, text) in texts(row, fields) {
+            let mut marked = String::new();
+            let mut last = 0;
+            for word in words(text) {
+                if self
+                    .terms
+                    .iter()
+                    .any(|term| term_score(term, &word.text) > 0.0)
+                {
+                    escape_html(&text[last..word.start], &mut marked);
+                    marked.push_str("<mark>");
+                    escape_html(&text[word.start..word.end], &mut marked);
+                    marked.push_str("</mark>");
+                    last = word.end;
+                }
+            }
+            if last > 0 {
+                escape_html(&text[last..], &mut marked);
+                highlights.insert(path, Value::String(marked));
+            }
+        }
+        Value::Object(highlights)
+    }
+}
+
+// highlights are meant to be shown as HTML, so the record text must not be able to add tags
+fn escape_html(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+}
+
+// the strings to search with their path
+fn texts<'a>(row: &'a Value, fields: Option<&[String]>) -> Vec<(String, &'a str)> {
+    let mut texts = vec![];
+    match fields {
+        Some(fields) => {
+            for field in fields {
+                for value in resolve(row, field) {
+                    collect_strings(value, field.clone(), &mut texts);
+                }
+            }
+        }
+        None => collect_strings(row, String::new(), &mut texts),
+    }
+    texts
+}
+
+fn collect_strings<'a>(value: &'a Value, path: String, out: &mut Vec<(String, &'a str)>) {
+    let join = |key: &str| match path.is_empty() {
+        true => key.to_string(),
+        false => format!("{}.{}", path, key),
+    };
+    match value {
+        Value::String(text) => out.push((path, text)),
+        Value::Array(arr) => {
+            for (i, value) in arr.iter().enumerate() {
+                collect_strings(value, join(&i.to_string()), out);
+            }
+        }
+        Value::Object(obj) => {
+            for (key, value) in obj {
+                collect_strings(value, join(key), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::Search;
+
+    #[test]
+    fn test_fuzzy_search_and_highlight() {
+        let rows = [
+            json!({ "id": 1, "name": "Zoë Adébáyọ̀", "city": "Lagos" }),
+            json!({ "id": 2, "name": "Alice Smith", "city": "Abuja" }),
+            json!({ "id": 3, "name": "Alicia Keys", "bio": "Moved to Lagos" }),
+        ];
+        let matches = |q: &str| -> Vec<(i64, f64)> {
+            let search = Search::new(q);
+            rows.iter()
+                .filter_map(|row| Some((row["id"].as_i64()?, search.score(row, None)?)))
+                .collect()
+        };
+
+        // accents and case are ignored
+        assert_eq!(matches("zoe adebayo").len(), 1);
+        // an exact word ranks above a typo in a longer one
+        let found = matches("alice");
+        assert_eq!(found.iter().map(|(id, _)| *id).collect::<Vec<_>>(), [2, 3]);
+        assert!(found[0].1 > found[1].1);
+        assert_eq!(
+            matches("alic lag")
+                .iter()
+                .map(|(id, _)| *id)
+                .collect::<Vec<_>>(),
+            [3]
+        );
+        assert_eq!(matches("smiht abuja").len(), 1);
+        assert!(matches("xyz").is_empty());
+
+        let fields = ["name".to_string()];
+        assert!(
+            Search::new("lagos")
+                .score(&rows[2], Some(&fields))
+                .is_none()
+        );
+        assert_eq!(
+            Search::new("lagos").highlight(&rows[2], None),
+            json!({ "bio": "Moved to <mark>Lagos</mark>" })
+        );
+        let row = json!({ "bio": "<img src=x onerror=alert(1)> Lagos & \"Abuja\"" });
+        assert_eq!(
+            Search::new("lagos").highlight(&row, None),
+            json!({
+                "bio": "&lt;img src=x onerror=alert(1)&gt; <mark>Lagos</mark> &amp; &quot;Abuja&quot;"
+            })
+        );
+    }
+}